@@ -0,0 +1,94 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use tokio::net::ToSocketAddrs;
+
+/// Address of a server, any address tokio resolves. The host name of the address is kept,
+/// TLS connections verify the server certificate against it.
+///
+/// ```rust
+/// use memento::Address;
+/// use std::net::SocketAddr;
+///
+/// assert_eq!(Some("cache.internal"), "cache.internal:11211".host());
+/// assert_eq!(Some("::1"), "[::1]:11211".host());
+/// assert_eq!(Some("cache.internal"), ("cache.internal", 11211).host());
+/// assert_eq!(None, "127.0.0.1:11211".parse::<SocketAddr>()?.host());
+/// # Ok::<(), std::net::AddrParseError>(())
+/// ```
+pub trait Address: ToSocketAddrs {
+    /// Host the address was given with, `None` for resolved addresses.
+    fn host(&self) -> Option<&str>;
+}
+
+impl<T: Address + ?Sized> Address for &T {
+    fn host(&self) -> Option<&str> {
+        (**self).host()
+    }
+}
+
+impl Address for str {
+    fn host(&self) -> Option<&str> {
+        let (host, _) = self.rsplit_once(':')?;
+
+        Some(host.trim_start_matches('[').trim_end_matches(']'))
+    }
+}
+
+impl Address for String {
+    fn host(&self) -> Option<&str> {
+        self.as_str().host()
+    }
+}
+
+impl Address for (&str, u16) {
+    fn host(&self) -> Option<&str> {
+        Some(self.0)
+    }
+}
+
+impl Address for (String, u16) {
+    fn host(&self) -> Option<&str> {
+        Some(&self.0)
+    }
+}
+
+impl Address for SocketAddr {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Address for SocketAddrV4 {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Address for SocketAddrV6 {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Address for (IpAddr, u16) {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Address for (Ipv4Addr, u16) {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Address for (Ipv6Addr, u16) {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Address for &[SocketAddr] {
+    fn host(&self) -> Option<&str> {
+        None
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Exponential backoff between reconnect attempts.
+///
+/// The delay before attempt `n` is `initial * 2^(n - 1)` capped by `max`, every delay is
+/// randomly shortened by up to a half so that clients do not reconnect in lockstep.
+/// The first attempt is made immediately.
+///
+/// ```rust
+/// use memento::Backoff;
+/// use std::time::Duration;
+///
+/// let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1)).attempts(3);
+///
+/// assert_eq!(3, backoff.max_attempts());
+/// assert_eq!(Duration::ZERO, backoff.delay(0));
+///
+/// for (attempt, max) in [(1, 100), (2, 200), (3, 400), (5, 1000), (10, 1000)] {
+///     let delay = backoff.delay(attempt);
+///
+///     assert!(delay <= Duration::from_millis(max));
+///     assert!(delay >= Duration::from_millis(max / 2));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct Backoff {
+    #[cfg_attr(feature = "serde", serde(with = "crate::timeout::duration"))]
+    initial: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::timeout::duration"))]
+    max: Duration,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "at_least_one"))]
+    attempts: u32,
+}
+
+impl Default for Backoff {
+    /// Five attempts starting with 50ms between them, at most 2s.
+    fn default() -> Self {
+        Self::new(Duration::from_millis(50), Duration::from_secs(2))
+    }
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            attempts: 5,
+        }
+    }
+
+    /// Attempts before giving up, at least one. The same applies to `attempts`
+    /// in configuration files.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "serde")]
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use memento::Backoff;
+    ///
+    /// let backoff: Backoff = toml::from_str(r#"attempts = 0"#)?;
+    ///
+    /// assert_eq!(1, backoff.max_attempts());
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(not(feature = "serde"))]
+    /// # fn main() {}
+    /// ```
+    pub fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Delay before the attempt, zero for the first one.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        if attempt == 0 {
+            return Duration::ZERO;
+        }
+
+        let delay = self
+            .initial
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max);
+
+        delay.mul_f64(1.0 - jitter() / 2.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn at_least_one<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    <u32 as serde::Deserialize>::deserialize(deserializer).map(|attempts| attempts.max(1))
+}
+
+/// Random number in `[0, 1)`, `RandomState` is seeded differently every time.
+fn jitter() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+
+    hasher.write_u8(0);
+
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,559 @@
+use crate::sasl::PLAIN;
+use crate::{Command, Credentials, Frame, Item, Key, MementoError};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::time::Duration;
+use tokio_util::codec::{Decoder, Encoder};
+
+const REQUEST: u8 = 0x80;
+const RESPONSE: u8 = 0x81;
+const HEADER_LEN: usize = 24;
+
+const GET: u8 = 0x00;
+const SET: u8 = 0x01;
+const ADD: u8 = 0x02;
+const REPLACE: u8 = 0x03;
+const DELETE: u8 = 0x04;
+const INCREMENT: u8 = 0x05;
+const DECREMENT: u8 = 0x06;
+const FLUSH: u8 = 0x08;
+const GETQ: u8 = 0x09;
+const NOOP: u8 = 0x0a;
+const VERSION: u8 = 0x0b;
+const GETK: u8 = 0x0c;
+const GETKQ: u8 = 0x0d;
+const APPEND: u8 = 0x0e;
+const PREPEND: u8 = 0x0f;
+const STAT: u8 = 0x10;
+const SETQ: u8 = 0x11;
+const ADDQ: u8 = 0x12;
+const REPLACEQ: u8 = 0x13;
+const DELETEQ: u8 = 0x14;
+const INCREMENTQ: u8 = 0x15;
+const DECREMENTQ: u8 = 0x16;
+const QUITQ: u8 = 0x17;
+const FLUSHQ: u8 = 0x18;
+const APPENDQ: u8 = 0x19;
+const PREPENDQ: u8 = 0x1a;
+const TOUCH: u8 = 0x1c;
+const GAT: u8 = 0x1d;
+const GATQ: u8 = 0x1e;
+const SASL_LIST_MECHS: u8 = 0x20;
+const SASL_AUTH: u8 = 0x21;
+const SASL_STEP: u8 = 0x22;
+const GATK: u8 = 0x23;
+const GATKQ: u8 = 0x24;
+
+const SUCCESS: u16 = 0x0000;
+const KEY_NOT_FOUND: u16 = 0x0001;
+const KEY_EXISTS: u16 = 0x0002;
+const VALUE_TOO_LARGE: u16 = 0x0003;
+const INVALID_ARGUMENTS: u16 = 0x0004;
+const ITEM_NOT_STORED: u16 = 0x0005;
+const NON_NUMERIC_VALUE: u16 = 0x0006;
+const AUTH_ERROR: u16 = 0x0020;
+const AUTH_CONTINUE: u16 = 0x0021;
+const UNKNOWN_COMMAND: u16 = 0x0081;
+
+/// Expiration of `incr` and `decr` that makes the server fail on a missing counter.
+const NO_INITIAL: u32 = 0xffffffff;
+
+/// Codec for the memcached binary protocol.
+///
+/// Responses are translated into the same frames the text protocol produces, so
+/// `CommandResp` and custom `ToCommandResponse` implementations work with both protocols.
+/// Retrieval commands are sent as a batch of quiet `getkq`/`gatkq` requests terminated
+/// by `noop`, writes with `noreply` use the quiet opcodes.
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use memento::{BinaryCodec, Command, Frame};
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// let mut codec = BinaryCodec::default();
+/// let mut buffer = BytesMut::new();
+///
+/// codec.encode(&Command::Version, &mut buffer)?;
+///
+/// assert_eq!(&[0x80, 0x0b], &buffer[..2]);
+///
+/// let mut response = BytesMut::from(&[0x81, 0x0b, 0, 0, 0, 0, 0, 0, 0, 0, 0, 6][..]);
+/// response.extend_from_slice(&[0; 12]);
+/// response.extend_from_slice(b"1.6.21");
+///
+/// assert_eq!(
+///     Some(vec![Frame::Line("VERSION 1.6.21".to_string())]),
+///     codec.decode(&mut response)?,
+/// );
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+///
+/// A round trip with a server speaking the binary protocol.
+///
+/// ```rust
+/// use memento::{Command, CommandResp, Item, MementoBuilder, MementoError, Protocol, Set};
+/// use std::collections::HashMap;
+/// use std::sync::{Arc, Mutex};
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+/// use tokio::net::TcpListener;
+///
+/// fn response(opcode: u8, status: u16, extras: &[u8], key: &[u8], value: &[u8]) -> Vec<u8> {
+///     let mut packet = vec![0x81, opcode];
+///
+///     packet.extend_from_slice(&(key.len() as u16).to_be_bytes());
+///     packet.extend_from_slice(&[extras.len() as u8, 0]);
+///     packet.extend_from_slice(&status.to_be_bytes());
+///     packet.extend_from_slice(&((extras.len() + key.len() + value.len()) as u32).to_be_bytes());
+///     packet.extend_from_slice(&[0; 12]);
+///     packet.extend_from_slice(extras);
+///     packet.extend_from_slice(key);
+///     packet.extend_from_slice(value);
+///     packet
+/// }
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+///     let addr = listener.local_addr()?;
+///     let opcodes = Arc::new(Mutex::new(Vec::new()));
+///     let log = opcodes.clone();
+///
+///     tokio::spawn(async move {
+///         let (mut stream, _) = listener.accept().await?;
+///         let mut store = HashMap::<Vec<u8>, Vec<u8>>::new();
+///         let mut header = [0; 24];
+///
+///         while stream.read_exact(&mut header).await.is_ok() {
+///             let opcode = header[1];
+///             let key_len = u16::from_be_bytes([header[2], header[3]]) as usize;
+///             let extras_len = header[4] as usize;
+///             let body_len = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+///             let mut body = vec![0; body_len as usize];
+///
+///             stream.read_exact(&mut body).await?;
+///
+///             let key = body[extras_len..extras_len + key_len].to_vec();
+///             let value = body[extras_len + key_len..].to_vec();
+///
+///             log.lock().unwrap().push(opcode);
+///
+///             let reply = match opcode {
+///                 // set, quiet set
+///                 0x01 | 0x11 => {
+///                     store.insert(key, value);
+///                     (opcode == 0x01).then(|| response(opcode, 0, &[], &[], &[]))
+///                 }
+///                 // add of an existing key
+///                 0x02 if store.contains_key(&key) => {
+///                     Some(response(opcode, 0x02, &[], &[], b"Exists"))
+///                 }
+///                 // delete of a missing key
+///                 0x04 if !store.contains_key(&key) => {
+///                     Some(response(opcode, 0x01, &[], &[], b"Not found"))
+///                 }
+///                 // increment of a value that is not a number
+///                 0x05 => Some(response(opcode, 0x06, &[], &[], b"Non-numeric value")),
+///                 // getkq replies on a hit only
+///                 0x0d => store.get(&key).map(|item| response(opcode, 0, &[0; 4], &key, item)),
+///                 0x0a => Some(response(opcode, 0, &[], &[], &[])),
+///                 0x0b => Some(response(opcode, 0, &[], &[], b"1.6.21")),
+///                 _ => Some(response(opcode, 0x81, &[], &[], b"Unknown command")),
+///             };
+///
+///             if let Some(reply) = reply {
+///                 stream.write_all(&reply).await?;
+///             }
+///         }
+///
+///         Ok::<_, std::io::Error>(())
+///     });
+///
+///     let mut memento = MementoBuilder::default()
+///         .protocol(Protocol::Binary)
+///         .connect(addr)
+///         .await?;
+///
+///     let response = memento.set("a".parse()?, Item::timeless("x")).await?;
+///
+///     assert!(matches!(response, CommandResp::Stored));
+///
+///     // sent as a quiet set, the `version` sent before the next command syncs the replies.
+///     let cmd = Command::Set(Set::new("b".parse()?, Item::timeless("y")).noreply());
+///
+///     assert!(matches!(memento.call(cmd).await?, CommandResp::NoResponse));
+///
+///     opcodes.lock().unwrap().clear();
+///
+///     let items = memento.get_multi(&["a".parse()?, "b".parse()?, "c".parse()?]).await?;
+///
+///     assert_eq!(vec![0x0b, 0x0d, 0x0d, 0x0d, 0x0a], *opcodes.lock().unwrap());
+///     assert_eq!(2, items.len());
+///     assert_eq!("y", items[&"b".parse()?].to_string());
+///
+///     // error statuses read as the replies of the text protocol.
+///     let added = memento.add("a".parse()?, Item::timeless("z")).await?;
+///     let deleted = memento.delete("c".parse()?).await?;
+///     let incremented = memento.incr("a".parse()?, 1).await;
+///
+///     assert!(matches!(added, CommandResp::NotStored));
+///     assert!(matches!(deleted, CommandResp::NotFound));
+///     assert!(matches!(incremented, Err(MementoError::ClientError(..))));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct BinaryCodec {
+    frames: Vec<Frame>,
+    error: Option<String>,
+}
+
+impl BinaryCodec {
+    /// Translates a single response packet, `None` means the packet is a part of a bigger
+    /// response: a quiet retrieval hit or a single stat.
+    fn response(
+        &mut self,
+        opcode: u8,
+        status: u16,
+        extras: Bytes,
+        key: Bytes,
+        value: Bytes,
+        cas: u64,
+    ) -> crate::Result<Option<Vec<Frame>>> {
+        let line = match (opcode, status) {
+            (GET | GETQ | GETK | GETKQ | GAT | GATQ | GATK | GATKQ, SUCCESS) => {
+                let flags = match extras.len() {
+                    4 => u32::from_be_bytes([extras[0], extras[1], extras[2], extras[3]]),
+                    _ => 0,
+                };
+
+                self.frames.push(Frame::Line(format!(
+                    "VALUE {key} {flags} {len} {cas}",
+                    key = String::from_utf8_lossy(&key),
+                    len = value.len(),
+                )));
+                self.frames.push(Frame::Data(value));
+
+                match opcode {
+                    GETQ | GETKQ | GATQ | GATKQ => return Ok(None),
+                    _ => return Ok(Some(self.end())),
+                }
+            }
+            (GETQ | GETKQ | GATQ | GATKQ, _) => {
+                self.error = Some(error(opcode, status, &value));
+
+                return Ok(None);
+            }
+            (NOOP, _) => return Ok(Some(self.end())),
+            (STAT, SUCCESS) if key.is_empty() => return Ok(Some(self.end())),
+            (STAT, SUCCESS) => {
+                self.frames.push(Frame::Line(format!(
+                    "STAT {name} {value}",
+                    name = String::from_utf8_lossy(&key),
+                    value = String::from_utf8_lossy(&value),
+                )));
+
+                return Ok(None);
+            }
+            (SET | SETQ | ADD | ADDQ | REPLACE | REPLACEQ, SUCCESS)
+            | (APPEND | APPENDQ | PREPEND | PREPENDQ, SUCCESS) => "STORED".to_string(),
+            (DELETE | DELETEQ, SUCCESS) => "DELETED".to_string(),
+            (INCREMENT | INCREMENTQ | DECREMENT | DECREMENTQ, SUCCESS) if value.len() == 8 => {
+                (&value[..]).get_u64().to_string()
+            }
+            (FLUSH | FLUSHQ, SUCCESS) => "OK".to_string(),
+            (TOUCH, SUCCESS) => "TOUCHED".to_string(),
+            (VERSION, SUCCESS) => format!("VERSION {}", String::from_utf8_lossy(&value)),
+            (SASL_LIST_MECHS, SUCCESS) => {
+                format!("SASL_MECHS {}", String::from_utf8_lossy(&value))
+            }
+            (SASL_AUTH | SASL_STEP, SUCCESS) => "AUTHENTICATED".to_string(),
+            (_, SUCCESS) => {
+                return Err(MementoError::InvalidResponse(format!(
+                    "unexpected binary response opcode {opcode:#04x}"
+                )))
+            }
+            (_, status) => error(opcode, status, &value),
+        };
+
+        Ok(Some(vec![Frame::Line(line)]))
+    }
+
+    /// Terminates a multi-packet response the same way the text protocol does.
+    fn end(&mut self) -> Vec<Frame> {
+        let mut frames = std::mem::take(&mut self.frames);
+
+        frames.push(Frame::Line(
+            self.error.take().unwrap_or_else(|| "END".to_string()),
+        ));
+
+        frames
+    }
+}
+
+/// Text protocol equivalent of an error status.
+fn error(opcode: u8, status: u16, value: &[u8]) -> String {
+    let message = String::from_utf8_lossy(value);
+
+    match (opcode, status) {
+        (GET | GETK | GAT | GATK, KEY_NOT_FOUND) => "END".to_string(),
+        (REPLACE | REPLACEQ | APPEND | APPENDQ | PREPEND | PREPENDQ, KEY_NOT_FOUND) => {
+            "NOT_STORED".to_string()
+        }
+        (_, KEY_NOT_FOUND) => "NOT_FOUND".to_string(),
+        (ADD | ADDQ, KEY_EXISTS) => "NOT_STORED".to_string(),
+        (_, KEY_EXISTS) => "EXISTS".to_string(),
+        (_, ITEM_NOT_STORED) => "NOT_STORED".to_string(),
+        (_, VALUE_TOO_LARGE) => format!("SERVER_ERROR {message}"),
+        (_, INVALID_ARGUMENTS | NON_NUMERIC_VALUE) => format!("CLIENT_ERROR {message}"),
+        (_, UNKNOWN_COMMAND) => "ERROR".to_string(),
+        (_, AUTH_ERROR) => format!("AUTH_ERROR {message}"),
+        (_, AUTH_CONTINUE) => format!("AUTH_CONTINUE {message}"),
+        (_, status) => format!("SERVER_ERROR {message} (status {status:#06x})"),
+    }
+}
+
+impl Decoder for BinaryCodec {
+    type Item = Vec<Frame>;
+    type Error = MementoError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> crate::Result<Option<Self::Item>> {
+        loop {
+            if src.len() < HEADER_LEN {
+                return Ok(None);
+            }
+
+            if src[0] != RESPONSE {
+                return Err(MementoError::InvalidResponse(format!(
+                    "unexpected binary magic {:#04x}",
+                    src[0]
+                )));
+            }
+
+            let body_len = u32::from_be_bytes([src[8], src[9], src[10], src[11]]) as usize;
+
+            if src.len() < HEADER_LEN + body_len {
+                src.reserve(HEADER_LEN + body_len - src.len());
+
+                return Ok(None);
+            }
+
+            let mut header = src.split_to(HEADER_LEN);
+            let mut body = src.split_to(body_len).freeze();
+
+            header.advance(1); // magic is already checked.
+
+            let opcode = header.get_u8();
+            let key_len = header.get_u16() as usize;
+            let extras_len = header.get_u8() as usize;
+            header.advance(1); // data type is reserved.
+            let status = header.get_u16();
+            header.advance(8); // body length is already known and opaque is not used.
+            let cas = header.get_u64();
+
+            if extras_len + key_len > body.len() {
+                return Err(MementoError::InvalidResponse(format!(
+                    "binary response body of {} bytes is too short",
+                    body.len()
+                )));
+            }
+
+            let extras = body.split_to(extras_len);
+            let key = body.split_to(key_len);
+
+            if let Some(frames) = self.response(opcode, status, extras, key, body, cas)? {
+                return Ok(Some(frames));
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> crate::Result<Option<Self::Item>> {
+        match self.decode(src)? {
+            Some(frames) => Ok(Some(frames)),
+            None if src.is_empty() && self.frames.is_empty() => Ok(None),
+            None => Err(MementoError::ConnectionReset),
+        }
+    }
+}
+
+impl Encoder<&Command> for BinaryCodec {
+    type Error = MementoError;
+
+    fn encode(&mut self, cmd: &Command, dst: &mut BytesMut) -> crate::Result<()> {
+        match cmd {
+            Command::Set(cmd) => {
+                storage(dst, quiet(SET, SETQ, cmd.noreply), &cmd.key, &cmd.item, 0)
+            }
+            Command::Add(cmd) => {
+                storage(dst, quiet(ADD, ADDQ, cmd.noreply), &cmd.key, &cmd.item, 0)
+            }
+            Command::Replace(cmd) => storage(
+                dst,
+                quiet(REPLACE, REPLACEQ, cmd.noreply),
+                &cmd.key,
+                &cmd.item,
+                0,
+            ),
+            Command::Cas(cmd) => storage(
+                dst,
+                quiet(SET, SETQ, cmd.noreply),
+                &cmd.key,
+                &cmd.item,
+                cmd.cas,
+            ),
+            Command::Append(cmd) => request(
+                dst,
+                quiet(APPEND, APPENDQ, cmd.noreply),
+                &[],
+                &cmd.key,
+                cmd.item.value(),
+                0,
+            ),
+            Command::Prepend(cmd) => request(
+                dst,
+                quiet(PREPEND, PREPENDQ, cmd.noreply),
+                &[],
+                &cmd.key,
+                cmd.item.value(),
+                0,
+            ),
+            Command::Get(key) => retrieval(dst, GETKQ, &[], std::slice::from_ref(key)),
+            Command::GetMulti(keys) | Command::Gets(keys) => retrieval(dst, GETKQ, &[], keys),
+            Command::Gat(cmd) | Command::Gats(cmd) => {
+                retrieval(dst, GATKQ, &expiration(cmd.ttl).to_be_bytes(), &cmd.keys)
+            }
+            Command::Incr(cmd) => counter(
+                dst,
+                quiet(INCREMENT, INCREMENTQ, cmd.noreply),
+                &cmd.key,
+                cmd.value,
+                cmd.initial,
+            ),
+            Command::Decr(cmd) => counter(
+                dst,
+                quiet(DECREMENT, DECREMENTQ, cmd.noreply),
+                &cmd.key,
+                cmd.value,
+                cmd.initial,
+            ),
+            Command::Delete(cmd) => request(
+                dst,
+                quiet(DELETE, DELETEQ, cmd.noreply),
+                &[],
+                &cmd.key,
+                &[],
+                0,
+            ),
+            // there is no quiet touch, the reply to a noreply touch is skipped on the next call.
+            Command::Touch(cmd) => request(
+                dst,
+                TOUCH,
+                &expiration(cmd.ttl).to_be_bytes(),
+                &cmd.key,
+                &[],
+                0,
+            ),
+            Command::FlushAll(cmd) => request(
+                dst,
+                quiet(FLUSH, FLUSHQ, cmd.noreply),
+                &cmd.delay
+                    .map(|delay| expiration(delay).to_be_bytes().to_vec())
+                    .unwrap_or_default(),
+                &Key::default(),
+                &[],
+                0,
+            ),
+            Command::Stats => request(dst, STAT, &[], &Key::default(), &[], 0),
+            Command::Version => request(dst, VERSION, &[], &Key::default(), &[], 0),
+            Command::Quit => request(dst, QUITQ, &[], &Key::default(), &[], 0),
+            Command::MetaGet(..)
+            | Command::MetaSet(..)
+            | Command::MetaDelete(..)
+            | Command::MetaArithmetic(..)
+            | Command::MetaNoop
+            | Command::MetaDebug(..) => {
+                return Err(MementoError::UnsupportedCommand(
+                    "meta commands require the text protocol".to_string(),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Asks for the SASL mechanisms supported by the server, the reply is `SASL_MECHS <mechanisms>`.
+pub(crate) fn sasl_list_mechs(dst: &mut BytesMut) {
+    request(dst, SASL_LIST_MECHS, &[], &Key::default(), &[], 0);
+}
+
+/// Starts the `PLAIN` exchange or continues it when `step` is set, the reply is either
+/// `AUTHENTICATED`, `AUTH_CONTINUE <challenge>` or `AUTH_ERROR <message>`.
+pub(crate) fn sasl_auth(
+    dst: &mut BytesMut,
+    credentials: &Credentials,
+    step: bool,
+) -> crate::Result<()> {
+    let opcode = if step { SASL_STEP } else { SASL_AUTH };
+
+    request(dst, opcode, &[], &PLAIN.parse()?, &credentials.plain(), 0);
+
+    Ok(())
+}
+
+fn quiet(opcode: u8, quiet: u8, noreply: bool) -> u8 {
+    if noreply {
+        quiet
+    } else {
+        opcode
+    }
+}
+
+fn expiration(ttl: Duration) -> u32 {
+    ttl.as_secs().try_into().unwrap_or(u32::MAX)
+}
+
+fn request(dst: &mut BytesMut, opcode: u8, extras: &[u8], key: &Key, value: &[u8], cas: u64) {
+    let key = key.to_string();
+
+    dst.reserve(HEADER_LEN + extras.len() + key.len() + value.len());
+    dst.put_u8(REQUEST);
+    dst.put_u8(opcode);
+    dst.put_u16(key.len() as u16);
+    dst.put_u8(extras.len() as u8);
+    dst.put_u8(0); // raw bytes data type.
+    dst.put_u16(0); // vbucket.
+    dst.put_u32((extras.len() + key.len() + value.len()) as u32);
+    dst.put_u32(0); // opaque.
+    dst.put_u64(cas);
+    dst.put(extras);
+    dst.put(key.as_bytes());
+    dst.put(value);
+}
+
+fn storage(dst: &mut BytesMut, opcode: u8, key: &Key, item: &Item, cas: u64) {
+    let mut extras = [0; 8];
+
+    extras[..4].copy_from_slice(&item.flags().to_be_bytes());
+    extras[4..].copy_from_slice(&expiration(item.ttl().unwrap_or_default()).to_be_bytes());
+
+    request(dst, opcode, &extras, key, item.value(), cas);
+}
+
+/// Sends a quiet request per key and a `noop` that marks the end of the hits.
+fn retrieval(dst: &mut BytesMut, opcode: u8, extras: &[u8], keys: &[Key]) {
+    for key in keys {
+        request(dst, opcode, extras, key, &[], 0);
+    }
+
+    request(dst, NOOP, &[], &Key::default(), &[], 0);
+}
+
+fn counter(dst: &mut BytesMut, opcode: u8, key: &Key, delta: u64, initial: Option<u64>) {
+    let mut extras = BytesMut::with_capacity(20);
+
+    extras.put_u64(delta);
+    extras.put_u64(initial.unwrap_or_default());
+    extras.put_u32(initial.map(|_| 0).unwrap_or(NO_INITIAL));
+
+    request(dst, opcode, &extras, key, &[], 0);
+}
@@ -0,0 +1,629 @@
+use crate::connection::{Connection, Options};
+#[cfg(feature = "tls")]
+use crate::TlsConfig;
+use crate::{Address, Backoff, Credentials, Memento, MementoError, Protocol, Timeouts};
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(unix)]
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{lookup_host, ToSocketAddrs};
+
+/// How `MementoBuilder::build` reaches the configured addresses.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Transport {
+    /// Addresses are `host:port` pairs.
+    #[default]
+    Tcp,
+    /// Addresses are paths of Unix domain sockets.
+    #[cfg(unix)]
+    Unix,
+}
+
+/// Configures how `Memento` talks to the server.
+///
+/// ```rust,no_run
+/// use memento::{Backoff, MementoBuilder, Protocol};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let memento = MementoBuilder::default()
+///         .addresses(["cache-1:11211", "cache-2:11211"])
+///         .protocol(Protocol::Binary)
+///         .connect_timeout(Duration::from_secs(1))
+///         .read_timeout(Duration::from_millis(100))
+///         .read_buffer_size(64 * 1024)
+///         .nodelay(true)
+///         .keepalive(Duration::from_secs(60))
+///         .key_prefix("app:")
+///         .reconnect(Backoff::default().attempts(10))
+///         .retry_idempotent(true)
+///         .build()
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// With the `serde` feature the builder can be read from a configuration file, durations
+/// are written as `"150ms"`, `"2s"`, `"1m"`, `"1h"` or as a number of milliseconds.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use memento::MementoBuilder;
+///
+/// let builder: MementoBuilder = toml::from_str(
+///     r#"
+///         addresses = ["localhost:11211"]
+///         protocol = "binary"
+///         connect_timeout = "1s"
+///         read_timeout = 100
+///         nodelay = true
+///         key_prefix = "app:"
+///         credentials = { username = "user", password = "secret" }
+///         reconnect = { initial = "10ms", max = "1s", attempts = 3 }
+///     "#,
+/// )?;
+///
+/// // durations too long for `Duration` are rejected.
+/// assert!(toml::from_str::<MementoBuilder>(r#"read_timeout = "9999999999999999999h""#).is_err());
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "serde"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Deserialize), serde(from = "Config"))]
+pub struct MementoBuilder {
+    addresses: Vec<String>,
+    transport: Transport,
+    options: Options,
+    #[cfg(feature = "tls")]
+    tls: Option<TlsConfig>,
+}
+
+impl MementoBuilder {
+    /// Adds an address used by `build`, the addresses are tried in order until one accepts
+    /// the connection.
+    pub fn address<A: Into<String>>(mut self, address: A) -> Self {
+        self.addresses.push(address.into());
+        self
+    }
+
+    /// Replaces the addresses used by `build`.
+    pub fn addresses<I>(mut self, addresses: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.addresses = addresses.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// How the addresses are interpreted, `Transport::Tcp` by default.
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Wire protocol, `Protocol::Ascii` by default.
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.options.protocol = protocol;
+        self
+    }
+
+    /// Authenticate with the SASL `PLAIN` mechanism right after connecting and after every
+    /// reconnect. Requires `Protocol::Binary`.
+    ///
+    /// ```rust,no_run
+    /// use memento::{Credentials, MementoBuilder, Protocol};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let memento = MementoBuilder::default()
+    ///         .protocol(Protocol::Binary)
+    ///         .credentials(Credentials::new("user", "secret"))
+    ///         .connect("localhost:11211")
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// The server may ask to continue with `step`, wrong credentials fail the connection.
+    ///
+    /// ```rust
+    /// use memento::{CommandResp, Credentials, MementoBuilder, MementoError, Protocol};
+    /// use std::sync::{Arc, Mutex};
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    /// use tokio::net::{TcpListener, TcpStream};
+    ///
+    /// fn response(opcode: u8, status: u16, value: &[u8]) -> Vec<u8> {
+    ///     let mut packet = vec![0x81, opcode, 0, 0, 0, 0];
+    ///
+    ///     packet.extend_from_slice(&status.to_be_bytes());
+    ///     packet.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    ///     packet.extend_from_slice(&[0; 12]);
+    ///     packet.extend_from_slice(value);
+    ///     packet
+    /// }
+    ///
+    /// /// Accepts `user` with `secret` after a second step, logs the opcodes.
+    /// async fn serve(mut stream: TcpStream, log: Arc<Mutex<Vec<u8>>>) -> std::io::Result<()> {
+    ///     let mut header = [0; 24];
+    ///
+    ///     while stream.read_exact(&mut header).await.is_ok() {
+    ///         let key_len = u16::from_be_bytes([header[2], header[3]]) as usize;
+    ///         let body_len = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+    ///         let mut body = vec![0; body_len as usize];
+    ///
+    ///         stream.read_exact(&mut body).await?;
+    ///         log.lock().unwrap().push(header[1]);
+    ///
+    ///         let reply = match (header[1], &body[key_len..]) {
+    ///             (0x20, _) => response(0x20, 0, b"SCRAM-SHA-256 PLAIN"),
+    ///             (0x21, b"\0user\0secret") => response(0x21, 0x21, b"continue"),
+    ///             (0x22, b"\0user\0secret") => response(0x22, 0, b"Authenticated"),
+    ///             (0x21 | 0x22, _) => response(header[1], 0x20, b"Auth failure"),
+    ///             (0x0b, _) => response(0x0b, 0, b"1.6.21"),
+    ///             (opcode, _) => response(opcode, 0x81, b"Unknown command"),
+    ///         };
+    ///
+    ///         stream.write_all(&reply).await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let addr = listener.local_addr()?;
+    ///     let opcodes = Arc::new(Mutex::new(Vec::new()));
+    ///     let log = opcodes.clone();
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok((stream, _)) = listener.accept().await {
+    ///             tokio::spawn(serve(stream, log.clone()));
+    ///         }
+    ///     });
+    ///
+    ///     let builder = MementoBuilder::default().protocol(Protocol::Binary);
+    ///
+    ///     let mut memento = builder
+    ///         .clone()
+    ///         .credentials(Credentials::new("user", "secret"))
+    ///         .connect(addr)
+    ///         .await?;
+    ///
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(..)));
+    ///     assert_eq!(vec![0x20, 0x21, 0x22, 0x0b], *opcodes.lock().unwrap());
+    ///
+    ///     // a new connection is authenticated again.
+    ///     opcodes.lock().unwrap().clear();
+    ///     memento.reconnect().await?;
+    ///
+    ///     assert_eq!(vec![0x20, 0x21, 0x22], *opcodes.lock().unwrap());
+    ///
+    ///     let result = builder
+    ///         .credentials(Credentials::new("user", "wrong"))
+    ///         .connect(addr)
+    ///         .await;
+    ///
+    ///     assert!(matches!(result, Err(MementoError::AuthenticationFailed(..))));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.options.credentials = Some(credentials);
+        self
+    }
+
+    /// Limit of connecting and authenticating, no limit by default.
+    ///
+    /// ```rust,no_run
+    /// use memento::MementoBuilder;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let memento = MementoBuilder::default()
+    ///         .connect_timeout(Duration::from_secs(1))
+    ///         .write_timeout(Duration::from_millis(100))
+    ///         .read_timeout(Duration::from_millis(100))
+    ///         .connect("localhost:11211")
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeouts = self.options.timeouts.connect(timeout);
+        self
+    }
+
+    /// Limit of writing a command, no limit by default.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeouts = self.options.timeouts.write(timeout);
+        self
+    }
+
+    /// Limit of reading a reply, no limit by default.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeouts = self.options.timeouts.read(timeout);
+        self
+    }
+
+    /// Capacity of the read buffer, 8KB by default. Larger values are read in several chunks.
+    pub fn read_buffer_size(mut self, size: usize) -> Self {
+        self.options.read_buffer_size = size;
+        self
+    }
+
+    /// Capacity of the write buffer, 8KB by default.
+    pub fn write_buffer_size(mut self, size: usize) -> Self {
+        self.options.write_buffer_size = size;
+        self
+    }
+
+    /// Sets `TCP_NODELAY` on TCP connections, disabled by default.
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.options.nodelay = nodelay;
+        self
+    }
+
+    /// Sends TCP keepalive probes once the connection has been idle for the given time,
+    /// `None` leaves the system default.
+    pub fn keepalive<T: Into<Option<Duration>>>(mut self, time: T) -> Self {
+        self.options.keepalive = time.into();
+        self
+    }
+
+    /// Puts the prefix in front of every key sent to the server and removes it from the keys
+    /// of the replies, so several applications can share a server. The prefixed key is still
+    /// limited to 250 bytes and base64 encoded meta keys cannot be prefixed.
+    pub fn key_prefix<P: Into<String>>(mut self, prefix: P) -> Self {
+        self.options.key_prefix = Some(prefix.into()).filter(|prefix| !prefix.is_empty());
+        self
+    }
+
+    /// Key prefix of the connections, empty when there is none.
+    pub(crate) fn prefix(&self) -> &str {
+        self.options.key_prefix()
+    }
+
+    pub(crate) fn timeouts(&self) -> Timeouts {
+        self.options.timeouts
+    }
+
+    /// A connection broken by a server restart, an I/O error or a timeout is reopened on the next
+    /// call with the backoff between attempts, `Backoff::default()` by default. `None` makes
+    /// the calls fail with `MementoError::BrokenConnection` instead, as they always do for
+    /// the streams passed to `from_stream` since those cannot be reopened.
+    ///
+    /// ```rust,no_run
+    /// use memento::{Backoff, MementoBuilder};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let memento = MementoBuilder::default()
+    ///         .reconnect(Backoff::new(Duration::from_millis(10), Duration::from_secs(1)).attempts(10))
+    ///         .retry_idempotent(true)
+    ///         .connect("localhost:11211")
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// A server that stalls, closes the connection, goes away and comes back.
+    ///
+    /// ```rust
+    /// use memento::{Backoff, CommandResp, MementoBuilder, MementoError};
+    /// use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    /// use std::time::{Duration, Instant};
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::{TcpListener, TcpStream};
+    ///
+    /// static ACCEPTED: AtomicUsize = AtomicUsize::new(0);
+    /// static CLOSED: AtomicBool = AtomicBool::new(false);
+    ///
+    /// /// Accepts the given number of connections, then stops listening.
+    /// async fn serve(listener: TcpListener, connections: usize) -> std::io::Result<()> {
+    ///     for _ in 0..connections {
+    ///         let (stream, _) = listener.accept().await?;
+    ///
+    ///         ACCEPTED.fetch_add(1, Ordering::Relaxed);
+    ///         tokio::spawn(reply(stream));
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// async fn reply(stream: TcpStream) -> std::io::Result<()> {
+    ///     let mut stream = BufReader::new(stream);
+    ///     let mut line = String::new();
+    ///
+    ///     while stream.read_line(&mut line).await? > 0 {
+    ///         match line.as_str() {
+    ///             "version\r\n" => stream.write_all(b"VERSION 1.6.21\r\n").await?,
+    ///             "get stall\r\n" => {}
+    ///             // the first `get close` closes the connection without a reply.
+    ///             "get close\r\n" if !CLOSED.swap(true, Ordering::Relaxed) => return Ok(()),
+    ///             _ => stream.write_all(b"END\r\n").await?,
+    ///         }
+    ///
+    ///         line.clear();
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let addr = listener.local_addr()?;
+    ///
+    ///     tokio::spawn(serve(listener, 3));
+    ///
+    ///     let mut memento = MementoBuilder::default()
+    ///         .read_timeout(Duration::from_millis(100))
+    ///         .reconnect(Backoff::new(Duration::from_millis(100), Duration::from_secs(1)).attempts(3))
+    ///         .retry_idempotent(true)
+    ///         .connect(addr)
+    ///         .await?;
+    ///
+    ///     // the timed out connection is reopened by the next call.
+    ///     assert!(matches!(memento.get("stall".parse()?).await, Err(MementoError::Timeout)));
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(..)));
+    ///     assert_eq!(2, ACCEPTED.load(Ordering::Relaxed));
+    ///
+    ///     // `get` is sent once again on a new connection.
+    ///     assert!(matches!(memento.get("close".parse()?).await?, CommandResp::NotFound));
+    ///     assert_eq!(3, ACCEPTED.load(Ordering::Relaxed));
+    ///
+    ///     // nothing listens anymore, the attempts wait 50-100ms and 100-200ms before failing.
+    ///     assert!(matches!(memento.get("stall".parse()?).await, Err(MementoError::Timeout)));
+    ///
+    ///     let start = Instant::now();
+    ///
+    ///     assert!(matches!(memento.version().await, Err(MementoError::IoError(..))));
+    ///     assert!(start.elapsed() >= Duration::from_millis(150));
+    ///
+    ///     // the server is back.
+    ///     tokio::spawn(serve(TcpListener::bind(addr).await?, 1));
+    ///
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(..)));
+    ///     assert_eq!(4, ACCEPTED.load(Ordering::Relaxed));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn reconnect<T: Into<Option<Backoff>>>(mut self, backoff: T) -> Self {
+        self.options.backoff = backoff.into();
+        self
+    }
+
+    /// Send `get`, `gets`, `touch` and `delete` once again after reconnecting when the connection
+    /// broke before their reply was read, disabled by default.
+    pub fn retry_idempotent(mut self, retry: bool) -> Self {
+        self.options.retry = retry;
+        self
+    }
+
+    /// Encrypt the TCP connections with TLS, see `TlsConfig`. Unix domain sockets
+    /// and streams passed to `from_stream` are not affected.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Connects to the configured addresses over the configured transport.
+    pub async fn build(self) -> crate::Result<Memento> {
+        if self.addresses.is_empty() {
+            return Err(MementoError::IoError(
+                "no address to connect to".to_string(),
+            ));
+        }
+
+        match self.transport {
+            // every server is verified against the host of its own address.
+            #[cfg(feature = "tls")]
+            Transport::Tcp if self.tls.is_some() => {
+                let mut failure = None;
+
+                for address in &self.addresses {
+                    match self.clone().connect(address.as_str()).await {
+                        Ok(memento) => return Ok(memento),
+                        Err(err) => failure = Some(err),
+                    }
+                }
+
+                Err(failure.expect("addresses are not empty"))
+            }
+            Transport::Tcp => {
+                let mut addrs = Vec::new();
+                let mut failure = None;
+
+                // a host that cannot be resolved does not prevent connecting to the others.
+                for address in &self.addresses {
+                    match lookup_host(address.as_str()).await {
+                        Ok(resolved) => addrs.extend(resolved),
+                        Err(err) => failure = Some(err),
+                    }
+                }
+
+                match failure {
+                    Some(err) if addrs.is_empty() => Err(err.into()),
+                    _ => self.connect(&addrs[..]).await,
+                }
+            }
+            #[cfg(unix)]
+            Transport::Unix => {
+                let mut failure = None;
+
+                for path in &self.addresses {
+                    match self.clone().connect_unix(path).await {
+                        Ok(memento) => return Ok(memento),
+                        Err(err) => failure = Some(err),
+                    }
+                }
+
+                Err(failure.expect("addresses are not empty"))
+            }
+        }
+    }
+
+    pub async fn connect<A: Address>(self, addr: A) -> crate::Result<Memento> {
+        let host = addr.host().map(ToString::to_string);
+
+        self.connect_host(addr, host.as_deref()).await
+    }
+
+    /// Same as `connect`, `host` is the name of the server when the address is resolved already.
+    #[cfg_attr(not(feature = "tls"), allow(unused_variables))]
+    pub(crate) async fn connect_host<A: ToSocketAddrs>(
+        self,
+        addr: A,
+        host: Option<&str>,
+    ) -> crate::Result<Memento> {
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &self.tls {
+            let connection = Connection::connect_tls(addr, host, tls, self.options).await?;
+
+            return Ok(Memento::from_connection(connection));
+        }
+
+        let connection = Connection::connect(addr, self.options).await?;
+
+        Ok(Memento::from_connection(connection))
+    }
+
+    /// Connects to the server listening on the Unix domain socket.
+    ///
+    /// ```rust,no_run
+    /// use memento::MementoBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let memento = MementoBuilder::default()
+    ///         .connect_unix("/var/run/memcached/memcached.sock")
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(unix)]
+    pub async fn connect_unix<P: AsRef<Path>>(self, path: P) -> crate::Result<Memento> {
+        let connection =
+            Connection::connect_unix(path.as_ref().to_path_buf(), self.options).await?;
+
+        Ok(Memento::from_connection(connection))
+    }
+
+    /// Wraps an already established stream, credentials are not used since the stream
+    /// may be authenticated already. The endpoint of the stream is unknown, so such
+    /// connection is not reopened when it breaks.
+    pub fn from_stream<S>(self, stream: S) -> Memento
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        Memento::from_connection(Connection::from_stream(stream, self.options))
+    }
+}
+
+/// Layout of the builder in configuration files.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    addresses: Vec<String>,
+    transport: Transport,
+    protocol: Protocol,
+    credentials: Option<Credentials>,
+    #[serde(deserialize_with = "crate::timeout::duration::option")]
+    connect_timeout: Option<Duration>,
+    #[serde(deserialize_with = "crate::timeout::duration::option")]
+    write_timeout: Option<Duration>,
+    #[serde(deserialize_with = "crate::timeout::duration::option")]
+    read_timeout: Option<Duration>,
+    read_buffer_size: usize,
+    write_buffer_size: usize,
+    nodelay: bool,
+    #[serde(deserialize_with = "crate::timeout::duration::option")]
+    keepalive: Option<Duration>,
+    key_prefix: Option<String>,
+    reconnect: Option<Backoff>,
+    retry_idempotent: bool,
+}
+
+#[cfg(feature = "serde")]
+impl Default for Config {
+    fn default() -> Self {
+        let options = Options::default();
+
+        Self {
+            addresses: Vec::new(),
+            transport: Transport::default(),
+            protocol: options.protocol,
+            credentials: options.credentials,
+            connect_timeout: options.timeouts.connect_timeout(),
+            write_timeout: options.timeouts.write_timeout(),
+            read_timeout: options.timeouts.read_timeout(),
+            read_buffer_size: options.read_buffer_size,
+            write_buffer_size: options.write_buffer_size,
+            nodelay: options.nodelay,
+            keepalive: options.keepalive,
+            key_prefix: options.key_prefix,
+            reconnect: options.backoff,
+            retry_idempotent: options.retry,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Config> for MementoBuilder {
+    fn from(config: Config) -> Self {
+        let mut builder = MementoBuilder::default()
+            .addresses(config.addresses)
+            .transport(config.transport)
+            .protocol(config.protocol)
+            .read_buffer_size(config.read_buffer_size)
+            .write_buffer_size(config.write_buffer_size)
+            .nodelay(config.nodelay)
+            .keepalive(config.keepalive)
+            .reconnect(config.reconnect)
+            .retry_idempotent(config.retry_idempotent);
+
+        builder.options.credentials = config.credentials;
+
+        if let Some(timeout) = config.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = config.write_timeout {
+            builder = builder.write_timeout(timeout);
+        }
+
+        if let Some(timeout) = config.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+
+        if let Some(prefix) = config.key_prefix {
+            builder = builder.key_prefix(prefix);
+        }
+
+        builder
+    }
+}
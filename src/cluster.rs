@@ -0,0 +1,1867 @@
+use crate::memento::chunks;
+use crate::{
+    Add, Append, Cas, Command, CommandResp, Decr, Delete, Distribution, FlushAll, Gat, Incr, Item,
+    Ketama, Key, Memento, MementoBuilder, MementoError, MetaArithmetic, MetaDebug, MetaDelete,
+    MetaGet, MetaResponse, MetaSet, MetaStatus, Prepend, Replace, Set, ToCommandResponse, Touch,
+};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::future::{poll_fn, Future};
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Port assumed when the server address has none.
+const DEFAULT_PORT: u16 = 11211;
+
+/// Period of the `version` probes of an ejected server unless configured otherwise.
+const PROBE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Node events kept for subscribers that fall behind.
+const EVENTS_CAPACITY: usize = 64;
+
+/// Node of `MementoCluster`, a server with twice the weight of another gets twice as many keys.
+///
+/// ```rust
+/// use memento::Server;
+///
+/// let server = "10.0.1.1".parse::<Server>()?.weight(2);
+///
+/// assert_eq!("10.0.1.1:11211", server.to_string());
+/// assert_eq!("cache:11212", "cache:11212".parse::<Server>()?.to_string());
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Server {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) weight: u32,
+}
+
+impl Server {
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            weight: 1,
+        }
+    }
+
+    /// Relative share of the keys, 1 by default and at least 1.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = weight.max(1);
+        self
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Display for Server {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// Parses `host:port` or `host`, the port is 11211 by default.
+impl FromStr for Server {
+    type Err = MementoError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (host, port) = match value.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') || host.ends_with(']') => (
+                host.trim_start_matches('[').trim_end_matches(']'),
+                port.parse()
+                    .map_err(|_| MementoError::InvalidServer(value.to_string()))?,
+            ),
+            _ => (value, DEFAULT_PORT),
+        };
+
+        if host.is_empty() {
+            return Err(MementoError::InvalidServer(value.to_string()));
+        }
+
+        Ok(Self::new(host, port))
+    }
+}
+
+/// Configures `MementoCluster`.
+///
+/// ```rust,no_run
+/// use memento::{MementoCluster, Protocol, Server};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let mut cluster = MementoCluster::builder()
+///         .memento(memento::Memento::builder().connect_timeout(Duration::from_secs(1)))
+///         .server("10.0.1.1:11211".parse()?)
+///         .server("10.0.1.2:11211".parse::<Server>()?.weight(2))
+///         .build()?;
+///
+///     cluster.set("x".parse()?, memento::Item::timeless("y")).await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ClusterBuilder {
+    memento: MementoBuilder,
+    servers: Vec<Server>,
+    distribution: Box<dyn Distribution>,
+    failure_threshold: Option<u32>,
+    probe_interval: Duration,
+    replicas: usize,
+    read_policy: ReadPolicy,
+    write_quorum: usize,
+}
+
+impl Default for ClusterBuilder {
+    fn default() -> Self {
+        Self {
+            memento: MementoBuilder::default(),
+            servers: Vec::new(),
+            distribution: Box::new(Ketama::default()),
+            failure_threshold: None,
+            probe_interval: PROBE_INTERVAL,
+            replicas: 1,
+            read_policy: ReadPolicy::default(),
+            write_quorum: 1,
+        }
+    }
+}
+
+impl ClusterBuilder {
+    /// Settings of the connection to every server, the addresses of the builder are not used.
+    pub fn memento(mut self, memento: MementoBuilder) -> Self {
+        self.memento = memento;
+        self
+    }
+
+    pub fn server(mut self, server: Server) -> Self {
+        self.servers.push(server);
+        self
+    }
+
+    pub fn servers<I: IntoIterator<Item = Server>>(mut self, servers: I) -> Self {
+        self.servers.extend(servers);
+        self
+    }
+
+    /// Strategy that maps keys to the servers, `Ketama` by default.
+    pub fn distribution<D: Distribution + 'static>(mut self, distribution: D) -> Self {
+        self.distribution = Box::new(distribution);
+        self
+    }
+
+    /// Number of consecutive connection failures or timeouts after which a server is ejected
+    /// and its keys go to the other servers until it answers a probe, `None` by default so
+    /// servers are never ejected.
+    ///
+    /// ```rust
+    /// use memento::{CommandResp, MementoCluster, MementoError, NodeEvent, Server};
+    /// use std::time::Duration;
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
+    ///
+    /// /// Misses every key.
+    /// async fn serve(listener: TcpListener) -> std::io::Result<()> {
+    ///     loop {
+    ///         let (stream, _) = listener.accept().await?;
+    ///
+    ///         tokio::spawn(async move {
+    ///             let mut stream = BufReader::new(stream);
+    ///             let mut line = String::new();
+    ///
+    ///             while stream.read_line(&mut line).await? > 0 {
+    ///                 match line.as_str() {
+    ///                     "version\r\n" => stream.write_all(b"VERSION 1.6.21\r\n").await?,
+    ///                     _ => stream.write_all(b"END\r\n").await?,
+    ///                 }
+    ///
+    ///                 line.clear();
+    ///             }
+    ///
+    ///             Ok::<_, std::io::Error>(())
+    ///         });
+    ///     }
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let live = Server::new("127.0.0.1", listener.local_addr()?.port());
+    ///
+    ///     tokio::spawn(serve(listener));
+    ///
+    ///     // the other server is down until its port is listened on again.
+    ///     let addr = TcpListener::bind("127.0.0.1:0").await?.local_addr()?;
+    ///     let dead = Server::new("127.0.0.1", addr.port());
+    ///
+    ///     let mut cluster = MementoCluster::builder()
+    ///         .servers([live.clone(), dead.clone()])
+    ///         .failure_threshold(Some(2))
+    ///         .probe_interval(Duration::from_millis(100))
+    ///         .build()?;
+    ///
+    ///     let mut events = cluster.events();
+    ///
+    ///     let key = (0..)
+    ///         .map(|i| format!("key:{i}").parse())
+    ///         .find(|key| key.as_ref().is_ok_and(|key| cluster.server(key) == &dead))
+    ///         .expect("some key is placed on every server")?;
+    ///
+    ///     for _ in 0..2 {
+    ///         assert!(matches!(cluster.get(key.clone()).await, Err(MementoError::IoError(..))));
+    ///     }
+    ///
+    ///     assert_eq!(NodeEvent::Ejected(dead.clone()), events.recv().await?);
+    ///     assert_eq!(vec![&dead], cluster.ejected().collect::<Vec<_>>());
+    ///
+    ///     // the key moved to the other server.
+    ///     assert!(matches!(cluster.get(key.clone()).await?, CommandResp::NotFound));
+    ///     assert_eq!(&live, cluster.server(&key));
+    ///
+    ///     tokio::spawn(serve(TcpListener::bind(addr).await?));
+    ///
+    ///     let restored = tokio::time::timeout(Duration::from_secs(5), events.recv()).await??;
+    ///
+    ///     assert_eq!(NodeEvent::Restored(dead.clone()), restored);
+    ///
+    ///     // the key is back on its own server.
+    ///     assert!(matches!(cluster.get(key.clone()).await?, CommandResp::NotFound));
+    ///     assert_eq!(&dead, cluster.server(&key));
+    ///     assert_eq!(0, cluster.ejected().count());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn failure_threshold(mut self, threshold: Option<u32>) -> Self {
+        self.failure_threshold = threshold.map(|threshold| threshold.max(1));
+        self
+    }
+
+    /// Period of the `version` probes of an ejected server, 2 seconds by default.
+    pub fn probe_interval(mut self, interval: Duration) -> Self {
+        self.probe_interval = interval;
+        self
+    }
+
+    /// Number of successive servers on the ring every key is written to, 1 by default.
+    ///
+    /// ```rust
+    /// use memento::{MementoCluster, ReadPolicy, Server};
+    ///
+    /// let cluster = MementoCluster::builder()
+    ///     .servers([
+    ///         "10.0.1.1:11211".parse()?,
+    ///         "10.0.1.2:11211".parse()?,
+    ///         "10.0.1.3:11211".parse::<Server>()?.weight(2),
+    ///         "cache-4:11212".parse()?,
+    ///     ])
+    ///     .replicas(3)
+    ///     .read_policy(ReadPolicy::Fastest)
+    ///     .write_quorum(2)
+    ///     .build()?;
+    ///
+    /// let replicas = cluster.replicas(&"foo".parse()?);
+    ///
+    /// assert_eq!(
+    ///     vec!["10.0.1.3:11211", "cache-4:11212", "10.0.1.1:11211"],
+    ///     replicas.iter().map(ToString::to_string).collect::<Vec<_>>(),
+    /// );
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn replicas(mut self, replicas: usize) -> Self {
+        self.replicas = replicas.max(1);
+        self
+    }
+
+    /// Order in which the replicas are read, `ReadPolicy::PrimaryFirst` by default.
+    ///
+    /// ```rust
+    /// use memento::{CommandResp, MementoCluster, ReadPolicy, Server};
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
+    ///
+    /// /// Answers every `get` with the value `y` or a miss.
+    /// async fn server(hit: bool) -> std::io::Result<Server> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let server = Server::new("127.0.0.1", listener.local_addr()?.port());
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok((stream, _)) = listener.accept().await {
+    ///             tokio::spawn(async move {
+    ///                 let mut stream = BufReader::new(stream);
+    ///                 let mut line = String::new();
+    ///
+    ///                 while stream.read_line(&mut line).await? > 0 {
+    ///                     let key = line.trim_end().trim_start_matches("get ");
+    ///
+    ///                     if hit {
+    ///                         let value = format!("VALUE {key} 0 1\r\ny\r\n");
+    ///
+    ///                         stream.write_all(value.as_bytes()).await?;
+    ///                     }
+    ///
+    ///                     stream.write_all(b"END\r\n").await?;
+    ///                     line.clear();
+    ///                 }
+    ///
+    ///                 Ok::<_, std::io::Error>(())
+    ///             });
+    ///         }
+    ///     });
+    ///
+    ///     Ok(server)
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     // nothing listens on the port of the first server.
+    ///     let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+    ///     let dead = Server::new("127.0.0.1", port);
+    ///     let servers = [dead, server(false).await?, server(true).await?];
+    ///
+    ///     // whatever the order, the replica that fails and the one that misses are skipped.
+    ///     for policy in [ReadPolicy::PrimaryFirst, ReadPolicy::Random, ReadPolicy::Fastest] {
+    ///         let mut cluster = MementoCluster::builder()
+    ///             .servers(servers.clone())
+    ///             .replicas(3)
+    ///             .read_policy(policy)
+    ///             .build()?;
+    ///
+    ///         for i in 0..10 {
+    ///             let response = cluster.get(format!("key:{i}").parse()?).await?;
+    ///
+    ///             assert!(matches!(
+    ///                 response,
+    ///                 CommandResp::Value { item, .. } if item.to_string() == "y"
+    ///             ));
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn read_policy(mut self, policy: ReadPolicy) -> Self {
+        self.read_policy = policy;
+        self
+    }
+
+    /// Number of replicas that must accept a write for it to succeed, 1 by default.
+    /// Never more than the replicas available, so a cluster with ejected servers may need less.
+    ///
+    /// ```rust
+    /// use memento::{CommandResp, Item, MementoCluster, Server};
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
+    ///
+    /// /// Answers every `set` with the reply.
+    /// async fn server(reply: &'static str) -> std::io::Result<Server> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let server = Server::new("127.0.0.1", listener.local_addr()?.port());
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok((stream, _)) = listener.accept().await {
+    ///             tokio::spawn(async move {
+    ///                 let mut stream = BufReader::new(stream);
+    ///                 let (mut line, mut data) = (String::new(), String::new());
+    ///
+    ///                 while stream.read_line(&mut line).await? > 0 {
+    ///                     stream.read_line(&mut data).await?;
+    ///                     stream.write_all(reply.as_bytes()).await?;
+    ///                     line.clear();
+    ///                     data.clear();
+    ///                 }
+    ///
+    ///                 Ok::<_, std::io::Error>(())
+    ///             });
+    ///         }
+    ///     });
+    ///
+    ///     Ok(server)
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let servers = [
+    ///         server("STORED\r\n").await?,
+    ///         server("STORED\r\n").await?,
+    ///         server("NOT_STORED\r\n").await?,
+    ///     ];
+    ///
+    ///     // two of the three replicas accept the write.
+    ///     for (quorum, stored) in [(1, true), (2, true), (3, false)] {
+    ///         let mut cluster = MementoCluster::builder()
+    ///             .servers(servers.clone())
+    ///             .replicas(3)
+    ///             .write_quorum(quorum)
+    ///             .build()?;
+    ///
+    ///         let response = cluster.set("x".parse()?, Item::timeless("y")).await?;
+    ///
+    ///         match stored {
+    ///             true => assert!(matches!(response, CommandResp::Stored)),
+    ///             false => assert!(matches!(response, CommandResp::NotStored)),
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_quorum(mut self, quorum: usize) -> Self {
+        self.write_quorum = quorum.max(1);
+        self
+    }
+
+    /// Places the servers, they are connected on first use.
+    pub fn build(self) -> crate::Result<MementoCluster> {
+        if self.servers.is_empty() {
+            return Err(MementoError::InvalidServer(
+                "cluster has no servers".to_string(),
+            ));
+        }
+
+        let mut cluster = MementoCluster {
+            health: Arc::new(Health {
+                ejected: self
+                    .servers
+                    .iter()
+                    .map(|_| AtomicBool::new(false))
+                    .collect(),
+                changed: AtomicBool::new(false),
+            }),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+            distribution: self.distribution,
+            nodes: self.servers.into_iter().map(Node::new).collect(),
+            live: Vec::new(),
+            memento: self.memento,
+            failure_threshold: self.failure_threshold,
+            probe_interval: self.probe_interval,
+            replicas: self.replicas,
+            read_policy: self.read_policy,
+            write_quorum: self.write_quorum,
+            seed: AtomicU64::new(RandomState::new().build_hasher().finish()),
+        };
+
+        cluster.place();
+
+        Ok(cluster)
+    }
+}
+
+/// Client of many servers, every key is stored on the server chosen by the `Distribution`.
+/// The default is the libmemcached compatible weighted ketama consistent hashing, so keys are
+/// placed the same way as by other clients built on libmemcached. The key prefix of the
+/// connection settings is not hashed.
+///
+/// Commands on a single key go to the server of the key, the keys of multi-key commands are
+/// grouped by server and the replies are merged, while `flush_all`, `version`, `stats` and `quit`
+/// are sent to every server at once and reply per server.
+///
+/// With `ClusterBuilder::failure_threshold` a server that keeps failing is ejected: its keys are
+/// placed over the remaining servers, and a background task probes it with `version` until it
+/// answers and gets its keys back. Ejected servers are skipped by `flush_all`, `version` and
+/// `stats`, and when every server is ejected the keys are placed over all of them again.
+///
+/// With `ClusterBuilder::replicas` every key is stored on that many successive servers: writes
+/// go to all of them at once and succeed when `ClusterBuilder::write_quorum` of them accept the
+/// write, replying as the first replica that accepted it. `NotStored`, `Exists` and `NotFound`
+/// do not count as accepted, a write short of the quorum replies as the first replica that
+/// rejected it, the primary when it did. Reads try the replicas in the order of the `ReadPolicy`
+/// until one has the key. Writes short of the quorum are not undone and the counters of `incr`
+/// and `decr` are independent on every replica, while `add`, `cas` and `update` go to the first
+/// replica only and copy the item it stored to the others.
+///
+/// ```rust,no_run
+/// use memento::{Item, MementoCluster, Server};
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let mut cluster = MementoCluster::new(["10.0.1.1:11211".parse()?, "10.0.1.2:11211".parse()?])?;
+///
+///     cluster.set("x".parse()?, Item::timeless("y")).await?;
+///
+///     for (key, item) in cluster.get_multi(&["x".parse()?, "z".parse()?]).await? {
+///         println!("{key}: {item}");
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MementoCluster {
+    memento: MementoBuilder,
+    distribution: Box<dyn Distribution>,
+    nodes: Vec<Node>,
+    /// Indexes of the nodes known to the distribution.
+    live: Vec<usize>,
+    health: Arc<Health>,
+    events: broadcast::Sender<NodeEvent>,
+    failure_threshold: Option<u32>,
+    probe_interval: Duration,
+    replicas: usize,
+    read_policy: ReadPolicy,
+    write_quorum: usize,
+    /// State of the generator behind `ReadPolicy::Random`.
+    seed: AtomicU64,
+}
+
+impl MementoCluster {
+    pub fn builder() -> ClusterBuilder {
+        ClusterBuilder::default()
+    }
+
+    /// Cluster of the servers with the default connection settings.
+    pub fn new<I: IntoIterator<Item = Server>>(servers: I) -> crate::Result<Self> {
+        ClusterBuilder::default().servers(servers).build()
+    }
+
+    /// Server the key is stored on, as of the last command.
+    ///
+    /// ```rust
+    /// use memento::{MementoCluster, Server};
+    ///
+    /// let cluster = MementoCluster::new([
+    ///     "10.0.1.1:11211".parse()?,
+    ///     "10.0.1.2:11211".parse()?,
+    ///     "10.0.1.3:11211".parse::<Server>()?.weight(2),
+    ///     "cache-4:11212".parse()?,
+    /// ])?;
+    ///
+    /// for (key, server) in [
+    ///     ("foo", "10.0.1.3:11211"),
+    ///     ("session:abc", "10.0.1.2:11211"),
+    ///     ("x", "cache-4:11212"),
+    /// ] {
+    ///     assert_eq!(server, cluster.server(&key.parse()?).to_string());
+    /// }
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn server(&self, key: &Key) -> &Server {
+        &self.nodes[self.index(key)].server
+    }
+
+    pub fn servers(&self) -> impl Iterator<Item = &Server> {
+        self.nodes.iter().map(|node| &node.server)
+    }
+
+    /// Servers holding the replicas of the key, the first one is `server`.
+    pub fn replicas(&self, key: &Key) -> Vec<&Server> {
+        self.replica_indexes(key)
+            .into_iter()
+            .map(|index| &self.nodes[index].server)
+            .collect()
+    }
+
+    /// Servers currently ejected.
+    pub fn ejected(&self) -> impl Iterator<Item = &Server> {
+        self.nodes
+            .iter()
+            .zip(&self.health.ejected)
+            .filter(|(_, ejected)| ejected.load(Ordering::Acquire))
+            .map(|(node, _)| &node.server)
+    }
+
+    /// Subscribes to ejections and restorations of the servers.
+    ///
+    /// ```rust,no_run
+    /// use memento::{MementoCluster, NodeEvent};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let cluster = MementoCluster::builder()
+    ///         .servers(["10.0.1.1:11211".parse()?, "10.0.1.2:11211".parse()?])
+    ///         .failure_threshold(Some(3))
+    ///         .build()?;
+    ///
+    ///     let mut events = cluster.events();
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok(event) = events.recv().await {
+    ///             match event {
+    ///                 NodeEvent::Ejected(server) => println!("{server} is down"),
+    ///                 NodeEvent::Restored(server) => println!("{server} is back"),
+    ///             }
+    ///         }
+    ///     });
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn events(&self) -> broadcast::Receiver<NodeEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn set(&mut self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.execute(Command::Set(Set::new(key, item))).await
+    }
+
+    /// Adds the item on the first replica and copies it to the others once it is stored,
+    /// the same goes for `cas`.
+    ///
+    /// ```rust
+    /// use memento::{CommandResp, Item, MementoCluster, Server};
+    /// use std::sync::{Arc, Mutex};
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
+    ///
+    /// /// Stores every `add` and `set`, rejects every `cas` and logs the commands.
+    /// async fn server() -> std::io::Result<(Server, Arc<Mutex<Vec<String>>>)> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let server = Server::new("127.0.0.1", listener.local_addr()?.port());
+    ///     let log = Arc::new(Mutex::new(Vec::new()));
+    ///     let lines = log.clone();
+    ///
+    ///     tokio::spawn(async move {
+    ///         let (stream, _) = listener.accept().await?;
+    ///         let mut stream = BufReader::new(stream);
+    ///         let (mut line, mut data) = (String::new(), String::new());
+    ///
+    ///         while stream.read_line(&mut line).await? > 0 {
+    ///             stream.read_line(&mut data).await?;
+    ///
+    ///             let reply = match line.starts_with("cas") {
+    ///                 true => "EXISTS\r\n",
+    ///                 false => "STORED\r\n",
+    ///             };
+    ///
+    ///             stream.write_all(reply.as_bytes()).await?;
+    ///             lines.lock().unwrap().push(line.split(' ').next().unwrap_or_default().to_string());
+    ///             line.clear();
+    ///             data.clear();
+    ///         }
+    ///
+    ///         Ok::<_, std::io::Error>(())
+    ///     });
+    ///
+    ///     Ok((server, log))
+    /// }
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (first, first_log) = server().await?;
+    ///     let (second, second_log) = server().await?;
+    ///
+    ///     let mut cluster = MementoCluster::builder()
+    ///         .servers([first.clone(), second])
+    ///         .replicas(2)
+    ///         .write_quorum(2)
+    ///         .build()?;
+    ///
+    ///     let key = "x".parse()?;
+    ///
+    ///     let (primary, replica) = match cluster.server(&key) == &first {
+    ///         true => (first_log, second_log),
+    ///         false => (second_log, first_log),
+    ///     };
+    ///
+    ///     let added = cluster.add(key.clone(), Item::timeless("y")).await?;
+    ///     let swapped = cluster.cas(key, Item::timeless("z"), 42).await?;
+    ///
+    ///     assert!(matches!(added, CommandResp::Stored));
+    ///     assert!(matches!(swapped, CommandResp::Exists));
+    ///
+    ///     assert_eq!(vec!["add", "cas"], *primary.lock().unwrap());
+    ///     assert_eq!(vec!["set"], *replica.lock().unwrap());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn add(&mut self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.execute(Command::Add(Add::new(key, item))).await
+    }
+
+    pub async fn append(&mut self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.execute(Command::Append(Append::new(key, item))).await
+    }
+
+    pub async fn prepend(&mut self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.execute(Command::Prepend(Prepend::new(key, item)))
+            .await
+    }
+
+    pub async fn replace(&mut self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.execute(Command::Replace(Replace::new(key, item)))
+            .await
+    }
+
+    pub async fn cas(&mut self, key: Key, item: Item, cas: u64) -> crate::Result<CommandResp> {
+        self.execute(Command::Cas(Cas::new(key, item, cas))).await
+    }
+
+    pub async fn get(&mut self, key: Key) -> crate::Result<CommandResp> {
+        self.execute(Command::Get(key)).await
+    }
+
+    /// Responds with `Values` of all servers or `NotFound` when none of the keys exist,
+    /// fails when any of the servers fails, see `gets_many`.
+    pub async fn gets(&mut self, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        self.gets_many(&keys).await.into_response()
+    }
+
+    /// Fetches the keys from their servers, missing keys are absent from the map.
+    /// Fails when any of the servers fails, see `get_many`.
+    pub async fn get_multi(&mut self, keys: &[Key]) -> crate::Result<HashMap<Key, Item>> {
+        self.get_many(keys).await.into_result()
+    }
+
+    /// Fetches the keys from all their servers at once, missing keys are absent from the values
+    /// and the keys of the servers that failed are reported in `failures`.
+    ///
+    /// ```rust,no_run
+    /// use memento::MementoCluster;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut cluster = MementoCluster::new(["10.0.1.1:11211".parse()?, "10.0.1.2:11211".parse()?])?;
+    ///
+    ///     let response = cluster.get_many(&["x".parse()?, "y".parse()?]).await;
+    ///
+    ///     for (key, item) in response.values() {
+    ///         println!("{key}: {item}");
+    ///     }
+    ///
+    ///     for failure in response.failures() {
+    ///         println!("{} failed for {:?}: {}", failure.server(), failure.keys(), failure.error());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// A server that is down fails its own keys only.
+    ///
+    /// ```rust
+    /// use memento::{Key, MementoCluster, MementoError, Server};
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let live = Server::new("127.0.0.1", listener.local_addr()?.port());
+    ///
+    ///     tokio::spawn(async move {
+    ///         let (stream, _) = listener.accept().await?;
+    ///         let mut stream = BufReader::new(stream);
+    ///         let mut line = String::new();
+    ///
+    ///         // every key exists with its name as the value.
+    ///         while stream.read_line(&mut line).await? > 0 {
+    ///             for key in line.split_whitespace().skip(1) {
+    ///                 let value = format!("VALUE {key} 0 {}\r\n{key}\r\n", key.len());
+    ///
+    ///                 stream.write_all(value.as_bytes()).await?;
+    ///             }
+    ///
+    ///             stream.write_all(b"END\r\n").await?;
+    ///             line.clear();
+    ///         }
+    ///
+    ///         Ok::<_, std::io::Error>(())
+    ///     });
+    ///
+    ///     // nothing listens on the port of the other server.
+    ///     let port = TcpListener::bind("127.0.0.1:0").await?.local_addr()?.port();
+    ///     let dead = Server::new("127.0.0.1", port);
+    ///
+    ///     let mut cluster = MementoCluster::new([live.clone(), dead.clone()])?;
+    ///
+    ///     let keys = (0..20)
+    ///         .map(|i| format!("key:{i}").parse())
+    ///         .collect::<Result<Vec<Key>, _>>()?;
+    ///
+    ///     let (mut hits, mut lost): (Vec<_>, Vec<_>) =
+    ///         keys.iter().cloned().partition(|key| cluster.server(key) == &live);
+    ///
+    ///     assert!(!hits.is_empty() && !lost.is_empty());
+    ///
+    ///     let response = cluster.get_many(&keys).await;
+    ///
+    ///     let mut values = response.values().keys().cloned().collect::<Vec<_>>();
+    ///
+    ///     values.sort_by_key(ToString::to_string);
+    ///     hits.sort_by_key(ToString::to_string);
+    ///
+    ///     assert_eq!(hits, values);
+    ///     assert!(response
+    ///         .values()
+    ///         .iter()
+    ///         .all(|(key, item)| item.to_string() == key.to_string()));
+    ///
+    ///     let [failure] = response.failures() else {
+    ///         panic!("a single server failed: {:?}", response.failures());
+    ///     };
+    ///
+    ///     let mut failed = failure.keys().to_vec();
+    ///
+    ///     failed.sort_by_key(ToString::to_string);
+    ///     lost.sort_by_key(ToString::to_string);
+    ///
+    ///     assert_eq!(&dead, failure.server());
+    ///     assert_eq!(lost, failed);
+    ///     assert!(matches!(failure.error(), MementoError::IoError(..)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_many(&mut self, keys: &[Key]) -> ClusterResponse<Item> {
+        let prefix = self.memento.prefix().to_string();
+
+        self.retrieve(keys.to_vec(), |keys| {
+            chunks(&keys, &prefix, "get")
+                .into_iter()
+                .map(Command::GetMulti)
+                .collect()
+        })
+        .await
+    }
+
+    /// Same as `get_many`, the items carry their cas.
+    pub async fn gets_many(&mut self, keys: &[Key]) -> ClusterResponse<Item> {
+        let prefix = self.memento.prefix().to_string();
+
+        self.retrieve(keys.to_vec(), |keys| {
+            chunks(&keys, &prefix, "gets")
+                .into_iter()
+                .map(Command::Gets)
+                .collect()
+        })
+        .await
+    }
+
+    /// Deletes the keys on all their servers at once, every key gets `Deleted` or `NotFound`.
+    pub async fn delete_many(&mut self, keys: &[Key]) -> ClusterResponse<CommandResp> {
+        self.each(keys.to_vec(), |key| Command::Delete(Delete::new(key)))
+            .await
+    }
+
+    /// Touches the keys on all their servers at once, every key gets `Touched` or `NotFound`.
+    pub async fn touch_many(
+        &mut self,
+        keys: &[Key],
+        ttl: Duration,
+    ) -> ClusterResponse<CommandResp> {
+        self.each(keys.to_vec(), |key| Command::Touch(Touch::new(key, ttl)))
+            .await
+    }
+
+    pub async fn incr(&mut self, key: Key, value: u64) -> crate::Result<CommandResp> {
+        self.execute(Command::Incr(Incr::new(key, value))).await
+    }
+
+    pub async fn decr(&mut self, key: Key, value: u64) -> crate::Result<CommandResp> {
+        self.execute(Command::Decr(Decr::new(key, value))).await
+    }
+
+    pub async fn delete(&mut self, key: Key) -> crate::Result<CommandResp> {
+        self.execute(Command::Delete(Delete::new(key))).await
+    }
+
+    pub async fn touch(&mut self, key: Key, ttl: Duration) -> crate::Result<CommandResp> {
+        self.execute(Command::Touch(Touch::new(key, ttl))).await
+    }
+
+    pub async fn gat(&mut self, ttl: Duration, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        let prefix = self.memento.prefix().to_string();
+        let cmd = format!("gat {}", ttl.as_secs());
+
+        self.retrieve(keys, |keys| {
+            chunks(&keys, &prefix, &cmd)
+                .into_iter()
+                .map(|keys| Command::Gat(Gat::new(ttl, keys)))
+                .collect()
+        })
+        .await
+        .into_response()
+    }
+
+    pub async fn gats(&mut self, ttl: Duration, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        let prefix = self.memento.prefix().to_string();
+        let cmd = format!("gats {}", ttl.as_secs());
+
+        self.retrieve(keys, |keys| {
+            chunks(&keys, &prefix, &cmd)
+                .into_iter()
+                .map(|keys| Command::Gats(Gat::new(ttl, keys)))
+                .collect()
+        })
+        .await
+        .into_response()
+    }
+
+    /// Read-modify-write of the key on its server, see `Memento::update`. The stored item is
+    /// then copied to the other replicas.
+    pub async fn update<F>(
+        &mut self,
+        key: Key,
+        retries: usize,
+        mut f: F,
+    ) -> crate::Result<CommandResp>
+    where
+        F: FnMut(&Item) -> Item,
+    {
+        self.refresh();
+
+        let replicas = self.replica_indexes(&key);
+        let mut stored = None;
+
+        let started = Instant::now();
+
+        let result = match self.nodes[replicas[0]].connect(&self.memento).await {
+            Ok(memento) => {
+                memento
+                    .update(key.clone(), retries, |item| {
+                        let item = f(item);
+                        stored = Some(item.clone());
+                        item
+                    })
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        self.nodes[replicas[0]].observe(started.elapsed());
+        self.record(replicas[0], result.as_ref().err());
+
+        match (result, stored) {
+            (Ok(CommandResp::Stored), Some(item)) if replicas.len() > 1 => {
+                self.copy(key, item, &replicas).await
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Invalidates all items on every server, a failed server does not stop the others.
+    ///
+    /// ```rust,no_run
+    /// use memento::MementoCluster;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut cluster = MementoCluster::new(["10.0.1.1:11211".parse()?, "10.0.1.2:11211".parse()?])?;
+    ///
+    ///     for (server, result) in cluster.flush_all(None).await {
+    ///         if let Err(err) = result {
+    ///             println!("{server} was not flushed: {err}");
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn flush_all(
+        &mut self,
+        delay: Option<Duration>,
+    ) -> Vec<(Server, crate::Result<CommandResp>)> {
+        self.broadcast(Command::FlushAll(FlushAll::new(delay)))
+            .await
+    }
+
+    pub async fn meta_get(&mut self, cmd: MetaGet) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaGet(cmd)).await
+    }
+
+    pub async fn meta_set(&mut self, cmd: MetaSet) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaSet(cmd)).await
+    }
+
+    pub async fn meta_delete(&mut self, cmd: MetaDelete) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaDelete(cmd)).await
+    }
+
+    pub async fn meta_arithmetic(&mut self, cmd: MetaArithmetic) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaArithmetic(cmd)).await
+    }
+
+    pub async fn meta_debug(&mut self, cmd: MetaDebug) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaDebug(cmd)).await
+    }
+
+    async fn meta(&mut self, cmd: Command) -> crate::Result<MetaResponse> {
+        match self.execute(cmd).await? {
+            CommandResp::Meta(response) => Ok(response),
+            resp => Err(MementoError::InvalidResponse(format!("{:?}", resp))),
+        }
+    }
+
+    /// Version of every server.
+    pub async fn version(&mut self) -> Vec<(Server, crate::Result<CommandResp>)> {
+        self.broadcast(Command::Version).await
+    }
+
+    /// Statistics of every server.
+    pub async fn stats(&mut self) -> Vec<(Server, crate::Result<CommandResp>)> {
+        self.broadcast(Command::Stats).await
+    }
+
+    /// Closes the open connections, the connections are dropped even when `quit` fails.
+    pub async fn quit(&mut self) -> Vec<(Server, crate::Result<CommandResp>)> {
+        let connected = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.memento.is_some())
+            .map(|(index, _)| index)
+            .collect();
+
+        let responses = self.send_each(connected, Command::Quit).await;
+
+        for node in &mut self.nodes {
+            node.memento = None;
+        }
+
+        responses
+    }
+
+    /// Sends the command to the servers of its key, fails with `MementoError::UnsupportedCommand`
+    /// for commands without a single key, and for `add` and `cas` when keys have replicas since
+    /// their replies cannot be checked before copying the item, see `add` and `cas` instead.
+    /// Reads fall back to the next replica on errors only, and every reply that is not an error
+    /// counts toward the write quorum.
+    pub async fn call<T: ToCommandResponse>(&mut self, cmd: Command) -> crate::Result<T> {
+        if self.replicas > 1 && is_primary_write(&cmd) {
+            return Err(MementoError::UnsupportedCommand(format!(
+                "{:?} cannot be replicated, use MementoCluster::add or MementoCluster::cas",
+                cmd
+            )));
+        }
+
+        self.route(cmd, |_| false, |_| true).await
+    }
+
+    /// Routes the commands of the typed methods, see `is_miss` and `is_accepted`.
+    async fn execute(&mut self, cmd: Command) -> crate::Result<CommandResp> {
+        match is_primary_write(&cmd) && self.replicas > 1 {
+            true => self.write_primary(cmd).await,
+            false => self.route(cmd, is_miss, is_accepted).await,
+        }
+    }
+
+    /// Sends `add` or `cas` to the first replica only, the others would decide on their own since
+    /// every replica has its own cas unique, then copies the stored item as `update` does.
+    /// Nothing is copied for `noreply` commands, their outcome is unknown.
+    async fn write_primary(&mut self, cmd: Command) -> crate::Result<CommandResp> {
+        let (key, item) = match &cmd {
+            Command::Add(Add { key, item, .. }) | Command::Cas(Cas { key, item, .. }) => {
+                (key.clone(), item.clone())
+            }
+            _ => return self.route(cmd, is_miss, is_accepted).await,
+        };
+
+        self.refresh();
+
+        let replicas = self.replica_indexes(&key);
+
+        match self.send(replicas[0], cmd).await {
+            Ok(CommandResp::Stored) if replicas.len() > 1 => self.copy(key, item, &replicas).await,
+            result => result,
+        }
+    }
+
+    /// Sets the item stored on the first replica on the other ones, the write succeeds once
+    /// enough of them stored it.
+    async fn copy(
+        &mut self,
+        key: Key,
+        item: Item,
+        replicas: &[usize],
+    ) -> crate::Result<CommandResp> {
+        let mut results = vec![(replicas[0], Ok(CommandResp::Stored))];
+
+        results.extend(
+            self.send_all(&replicas[1..], Command::Set(Set::new(key, item)))
+                .await,
+        );
+
+        self.quorum(results, is_accepted).map_err(|(_, err)| err)
+    }
+
+    /// Writes to all replicas of the key, reads the replicas until one has the key.
+    async fn route<T: ToCommandResponse>(
+        &mut self,
+        cmd: Command,
+        miss: fn(&T) -> bool,
+        accepted: fn(&T) -> bool,
+    ) -> crate::Result<T> {
+        let Some(key) = cmd.key() else {
+            return Err(MementoError::UnsupportedCommand(format!(
+                "{:?} cannot be routed to a single server",
+                cmd
+            )));
+        };
+
+        self.refresh();
+
+        let replicas = self.replica_indexes(key);
+
+        if is_read(&cmd) {
+            return self.read(self.read_order(replicas), cmd, miss).await;
+        }
+
+        if replicas.len() == 1 {
+            return self.send(replicas[0], cmd).await;
+        }
+
+        let results = self.send_all(&replicas, cmd).await;
+
+        self.quorum(results, accepted).map_err(|(_, err)| err)
+    }
+
+    /// Tries the servers in turn until one has the key, a miss is preferred to an error.
+    async fn read<T: ToCommandResponse>(
+        &mut self,
+        indexes: Vec<usize>,
+        cmd: Command,
+        miss: fn(&T) -> bool,
+    ) -> crate::Result<T> {
+        let mut reply = None;
+
+        for index in indexes {
+            match self.send::<T>(index, cmd.clone()).await {
+                Ok(resp) if !miss(&resp) => return Ok(resp),
+                Ok(resp) => reply = Some(Ok(resp)),
+                Err(err) if reply.is_none() => reply = Some(Err(err)),
+                Err(..) => {}
+            }
+        }
+
+        reply.expect("every key has a replica")
+    }
+
+    async fn send<T: ToCommandResponse>(&mut self, index: usize, cmd: Command) -> crate::Result<T> {
+        let started = Instant::now();
+
+        let result = match self.nodes[index].connect(&self.memento).await {
+            Ok(memento) => memento.call(cmd).await,
+            Err(err) => Err(err),
+        };
+
+        self.nodes[index].observe(started.elapsed());
+        self.record(index, result.as_ref().err());
+
+        result
+    }
+
+    /// Sends the command to all the servers at once, the replies keep the order of the servers.
+    async fn send_all<T: ToCommandResponse>(
+        &mut self,
+        indexes: &[usize],
+        cmd: Command,
+    ) -> Vec<(usize, crate::Result<T>)> {
+        let builder = &self.memento;
+
+        let requests = self
+            .nodes
+            .iter_mut()
+            .enumerate()
+            .filter(|(index, _)| indexes.contains(index))
+            .map(|(index, node)| {
+                let cmd = cmd.clone();
+
+                async move {
+                    let started = Instant::now();
+
+                    let result = match node.connect(builder).await {
+                        Ok(memento) => memento.call(cmd).await,
+                        Err(err) => Err(err),
+                    };
+
+                    node.observe(started.elapsed());
+
+                    (index, result)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut results = join_all(requests).await;
+
+        for (index, result) in &results {
+            self.record(*index, result.as_ref().err());
+        }
+
+        results.sort_by_key(|(index, _)| indexes.iter().position(|other| other == index));
+
+        results
+    }
+
+    /// The first accepted reply when enough replicas accepted the write. Otherwise the first
+    /// reply that was not accepted as is, so the primary's `NotStored`, `Exists` or error
+    /// when it rejected the write, an error comes with its server.
+    fn quorum<T>(
+        &self,
+        results: Vec<(usize, crate::Result<T>)>,
+        accepted: fn(&T) -> bool,
+    ) -> Result<T, (usize, MementoError)> {
+        let quorum = self.write_quorum.min(results.len());
+        let (mut count, mut reply, mut rejected) = (0, None, None);
+
+        for (index, result) in results {
+            match result {
+                Ok(resp) if accepted(&resp) => {
+                    count += 1;
+                    reply.get_or_insert(resp);
+                }
+                Ok(resp) => {
+                    rejected.get_or_insert(Ok(resp));
+                }
+                Err(err) => {
+                    rejected.get_or_insert(Err((index, err)));
+                }
+            }
+        }
+
+        match (reply, rejected) {
+            (Some(reply), _) if count >= quorum => Ok(reply),
+            (_, Some(rejected)) => rejected,
+            _ => unreachable!("a write short of the quorum has a rejected reply"),
+        }
+    }
+
+    fn index(&self, key: &Key) -> usize {
+        self.live[self.distribution.server(key)]
+    }
+
+    /// Servers of the replicas of the key, the primary first, followed by the next servers
+    /// when the distribution returns fewer.
+    fn replica_indexes(&self, key: &Key) -> Vec<usize> {
+        let count = self.replicas.min(self.live.len());
+
+        let mut positions = self.distribution.replicas(key, count);
+
+        positions.truncate(count);
+
+        if positions.is_empty() {
+            positions.push(self.distribution.server(key));
+        }
+
+        let mut next = positions[0];
+
+        while positions.len() < count {
+            next = (next + 1) % self.live.len();
+
+            if !positions.contains(&next) {
+                positions.push(next);
+            }
+        }
+
+        positions
+            .into_iter()
+            .map(|position| self.live[position])
+            .collect()
+    }
+
+    /// Replicas in the order they are read.
+    fn read_order(&self, mut indexes: Vec<usize>) -> Vec<usize> {
+        match self.read_policy {
+            ReadPolicy::PrimaryFirst => {}
+            ReadPolicy::Random => {
+                let offset = self.random() as usize % indexes.len();
+
+                indexes.rotate_left(offset);
+            }
+            ReadPolicy::Fastest => {
+                indexes.sort_by_key(|index| self.nodes[*index].latency.unwrap_or_default())
+            }
+        }
+
+        indexes
+    }
+
+    /// Next number of the splitmix64 sequence, seeded randomly when the cluster is built.
+    fn random(&self) -> u64 {
+        let mut z = self
+            .seed
+            .fetch_add(0x9e3779b97f4a7c15, Ordering::Relaxed)
+            .wrapping_add(0x9e3779b97f4a7c15);
+
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Places the servers that are not ejected, or all of them when every one is.
+    fn place(&mut self) {
+        self.live = (0..self.nodes.len())
+            .filter(|index| !self.health.ejected[*index].load(Ordering::Acquire))
+            .collect();
+
+        if self.live.is_empty() {
+            self.live = (0..self.nodes.len()).collect();
+        }
+
+        let servers = self
+            .live
+            .iter()
+            .map(|index| self.nodes[*index].server.clone())
+            .collect::<Vec<_>>();
+
+        self.distribution.update(&servers);
+    }
+
+    /// Places the servers again when a probe restored one of them.
+    fn refresh(&mut self) {
+        if self.health.changed.swap(false, Ordering::AcqRel) {
+            self.place();
+        }
+    }
+
+    /// Counts consecutive failures of the server and ejects it once there are too many.
+    fn record(&mut self, index: usize, error: Option<&MementoError>) {
+        let Some(threshold) = self.failure_threshold else {
+            return;
+        };
+
+        let node = &mut self.nodes[index];
+
+        match error {
+            Some(err) if is_node_failure(err) => node.failures += 1,
+            _ => node.failures = 0,
+        }
+
+        if node.failures < threshold || self.health.ejected[index].swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        node.failures = 0;
+        node.memento = None;
+
+        let _ = self.events.send(NodeEvent::Ejected(node.server.clone()));
+
+        tokio::spawn(probe(
+            Arc::downgrade(&self.health),
+            index,
+            node.server.clone(),
+            self.memento.clone(),
+            self.probe_interval,
+            self.events.clone(),
+        ));
+
+        self.place();
+    }
+
+    /// Sends the retrieval commands to the servers of the keys at once and merges the hits,
+    /// the keys missing on a server are retried on their next replica.
+    async fn retrieve<F>(&mut self, keys: Vec<Key>, cmds: F) -> ClusterResponse<Item>
+    where
+        F: Fn(Vec<Key>) -> Vec<Command>,
+    {
+        self.refresh();
+
+        let mut response = ClusterResponse::default();
+        let mut missed = HashSet::new();
+        let mut errors = HashMap::new();
+
+        let mut pending = keys
+            .into_iter()
+            .map(|key| {
+                let replicas = self.read_order(self.replica_indexes(&key));
+
+                (key, replicas)
+            })
+            .collect::<Vec<_>>();
+
+        for round in 0..self.replicas {
+            let mut groups = BTreeMap::<usize, Vec<Key>>::new();
+
+            for (key, replicas) in &pending {
+                if let Some(index) = replicas.get(round) {
+                    groups.entry(*index).or_default().push(key.clone());
+                }
+            }
+
+            if groups.is_empty() {
+                break;
+            }
+
+            for (index, keys, result) in self.fan_out(groups, &cmds).await {
+                match result {
+                    Ok(CommandResp::Values(hits)) => {
+                        response.values.extend(hits);
+                        missed.extend(keys);
+                    }
+                    Ok(CommandResp::NotFound) => missed.extend(keys),
+                    Ok(resp) => {
+                        let err = MementoError::InvalidResponse(format!("{:?}", resp));
+
+                        for key in keys {
+                            errors.entry(key).or_insert((index, err.clone()));
+                        }
+                    }
+                    Err(err) => {
+                        for key in keys {
+                            errors.entry(key).or_insert((index, err.clone()));
+                        }
+                    }
+                }
+            }
+
+            pending.retain(|(key, _)| !response.values.contains_key(key));
+        }
+
+        for (key, _) in pending {
+            if missed.contains(&key) {
+                continue;
+            }
+
+            if let Some((index, err)) = errors.remove(&key) {
+                response.fail(self.nodes[index].server.clone(), vec![key], err);
+            }
+        }
+
+        response
+    }
+
+    /// Sends a command per key to all replicas of the key at once and collects the reply of
+    /// every key that reached the write quorum.
+    async fn each<F>(&mut self, keys: Vec<Key>, cmd: F) -> ClusterResponse<CommandResp>
+    where
+        F: Fn(Key) -> Command,
+    {
+        self.refresh();
+
+        let mut groups = BTreeMap::<usize, Vec<Key>>::new();
+        let mut replies = HashMap::<Key, Vec<(usize, crate::Result<CommandResp>)>>::new();
+
+        for key in keys {
+            for index in self.replica_indexes(&key) {
+                groups.entry(index).or_default().push(key.clone());
+            }
+
+            replies.insert(key, Vec::new());
+        }
+
+        let cmds = |keys: Vec<Key>| keys.into_iter().map(&cmd).collect();
+
+        for (index, keys, result) in self.fan_out(groups, &cmds).await {
+            match result {
+                Ok(resp) => {
+                    if let Some(key) = keys.into_iter().next() {
+                        replies.entry(key).or_default().push((index, Ok(resp)));
+                    }
+                }
+                // a server that failed to connect replies once for all its keys.
+                Err(err) => {
+                    for key in keys {
+                        replies
+                            .entry(key)
+                            .or_default()
+                            .push((index, Err(err.clone())));
+                    }
+                }
+            }
+        }
+
+        let mut response = ClusterResponse::default();
+
+        for (key, mut results) in replies {
+            let replicas = self.replica_indexes(&key);
+
+            results.sort_by_key(|(index, _)| replicas.iter().position(|other| other == index));
+
+            match self.quorum(results, is_accepted) {
+                Ok(resp) => {
+                    response.values.insert(key, resp);
+                }
+                Err((index, err)) => {
+                    response.fail(self.nodes[index].server.clone(), vec![key], err)
+                }
+            }
+        }
+
+        response
+    }
+
+    /// Pipelines the commands of every server over all servers at once, each reply comes with
+    /// the server and the keys of its command.
+    async fn fan_out<F>(
+        &mut self,
+        mut groups: BTreeMap<usize, Vec<Key>>,
+        cmds: &F,
+    ) -> Vec<(usize, Vec<Key>, crate::Result<CommandResp>)>
+    where
+        F: Fn(Vec<Key>) -> Vec<Command>,
+    {
+        let builder = &self.memento;
+
+        let requests = self
+            .nodes
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, node)| {
+                let cmds = cmds(groups.remove(&index)?);
+
+                Some(async move {
+                    let keys = cmds.iter().map(Command::keys).collect::<Vec<_>>();
+                    let started = Instant::now();
+
+                    let results = match node.connect(builder).await {
+                        Ok(memento) => memento.call_many::<CommandResp>(cmds).await,
+                        Err(err) => {
+                            let keys = keys.into_iter().flatten().collect();
+
+                            return (index, vec![(index, keys, Err(err))]);
+                        }
+                    };
+
+                    node.observe(started.elapsed());
+
+                    let replies = keys
+                        .into_iter()
+                        .zip(results)
+                        .map(|(keys, result)| (index, keys, result))
+                        .collect::<Vec<_>>();
+
+                    (index, replies)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut replies = Vec::new();
+
+        for (index, node_replies) in join_all(requests).await {
+            let error = node_replies
+                .iter()
+                .filter_map(|(_, _, result)| result.as_ref().err())
+                .find(|err| is_node_failure(err))
+                .cloned();
+
+            self.record(index, error.as_ref());
+
+            replies.extend(node_replies);
+        }
+
+        replies
+    }
+
+    /// Sends the command to every server that is not ejected.
+    async fn broadcast(&mut self, cmd: Command) -> Vec<(Server, crate::Result<CommandResp>)> {
+        self.refresh();
+
+        self.send_each(self.live.clone(), cmd).await
+    }
+
+    /// Sends the command to all the servers at once, the replies keep the order of the servers.
+    async fn send_each(
+        &mut self,
+        indexes: Vec<usize>,
+        cmd: Command,
+    ) -> Vec<(Server, crate::Result<CommandResp>)> {
+        let groups = indexes
+            .into_iter()
+            .map(|index| (index, Vec::new()))
+            .collect();
+
+        self.fan_out(groups, &|_| vec![cmd.clone()])
+            .await
+            .into_iter()
+            .map(|(index, _, result)| (self.nodes[index].server.clone(), result))
+            .collect()
+    }
+}
+
+/// Replies of a command sent to many servers, the keys of the servers that failed are reported
+/// apart instead of failing the whole command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterResponse<T> {
+    values: HashMap<Key, T>,
+    failures: Vec<NodeFailure>,
+}
+
+impl<T> Default for ClusterResponse<T> {
+    fn default() -> Self {
+        Self {
+            values: HashMap::new(),
+            failures: Vec::new(),
+        }
+    }
+}
+
+impl<T> ClusterResponse<T> {
+    pub fn values(&self) -> &HashMap<Key, T> {
+        &self.values
+    }
+
+    pub fn into_values(self) -> HashMap<Key, T> {
+        self.values
+    }
+
+    pub fn failures(&self) -> &[NodeFailure] {
+        &self.failures
+    }
+
+    /// Whether every server replied.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Values when every server replied, the error of the first failed server otherwise.
+    pub fn into_result(mut self) -> crate::Result<HashMap<Key, T>> {
+        match self.failures.is_empty() {
+            true => Ok(self.values),
+            false => Err(self.failures.swap_remove(0).error),
+        }
+    }
+
+    fn fail(&mut self, server: Server, keys: Vec<Key>, error: MementoError) {
+        match self
+            .failures
+            .iter_mut()
+            .find(|failure| failure.server == server && failure.error == error)
+        {
+            Some(failure) => failure.keys.extend(keys),
+            None => self.failures.push(NodeFailure {
+                server,
+                keys,
+                error,
+            }),
+        }
+    }
+}
+
+impl ClusterResponse<Item> {
+    /// `Values` of all servers or `NotFound`, as a single server responds.
+    fn into_response(self) -> crate::Result<CommandResp> {
+        let values = self.into_result()?;
+
+        match values.is_empty() {
+            true => Ok(CommandResp::NotFound),
+            false => Ok(CommandResp::Values(values.into_iter().collect())),
+        }
+    }
+}
+
+/// Keys a server failed to process, along with the error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeFailure {
+    server: Server,
+    keys: Vec<Key>,
+    error: MementoError,
+}
+
+impl NodeFailure {
+    pub fn server(&self) -> &Server {
+        &self.server
+    }
+
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+
+    pub fn error(&self) -> &MementoError {
+        &self.error
+    }
+}
+
+/// Polls all futures until every one of them completes, the outputs keep the order of the futures.
+async fn join_all<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+    let mut futures = futures.into_iter().map(Box::pin).collect::<Vec<_>>();
+    let mut outputs = futures.iter().map(|_| None).collect::<Vec<_>>();
+
+    poll_fn(|cx| {
+        let mut ready = true;
+
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_some() {
+                continue;
+            }
+
+            match future.as_mut().poll(cx) {
+                Poll::Ready(value) => *output = Some(value),
+                Poll::Pending => ready = false,
+            }
+        }
+
+        match ready {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    })
+    .await;
+
+    outputs.into_iter().flatten().collect()
+}
+
+/// Order in which `MementoCluster` reads the replicas of a key, the next replica is tried when
+/// one misses the key or fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReadPolicy {
+    /// The first replica, then the others in the order of the ring.
+    #[default]
+    PrimaryFirst,
+    /// A replica picked at random for every read, then the ones following it.
+    Random,
+    /// The replica with the lowest recent latency, then the slower ones.
+    Fastest,
+}
+
+/// Change of the state of a server in `MementoCluster`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeEvent {
+    /// The server failed too many times in a row, its keys moved to the other servers.
+    Ejected(Server),
+    /// The ejected server answered a probe and got its keys back.
+    Restored(Server),
+}
+
+/// Ejected servers, shared with the probes.
+#[derive(Debug)]
+struct Health {
+    ejected: Vec<AtomicBool>,
+    /// Whether a probe restored a server since the servers were placed.
+    changed: AtomicBool,
+}
+
+/// Commands that only read the key, they are sent to a single replica at a time.
+fn is_read(cmd: &Command) -> bool {
+    matches!(
+        cmd,
+        Command::Get(..) | Command::MetaGet(..) | Command::MetaDebug(..)
+    )
+}
+
+/// Writes that depend on the state of the item, every replica would decide on its own.
+fn is_primary_write(cmd: &Command) -> bool {
+    matches!(cmd, Command::Add(..) | Command::Cas(..))
+}
+
+/// Replies after which the next replica is read.
+fn is_miss(resp: &CommandResp) -> bool {
+    match resp {
+        CommandResp::NotFound => true,
+        CommandResp::Meta(resp) => matches!(resp.status(), MetaStatus::Miss | MetaStatus::NotFound),
+        _ => false,
+    }
+}
+
+/// Replies that count toward the write quorum. Replies to `noreply` commands are unknown,
+/// so they count as well.
+fn is_accepted(resp: &CommandResp) -> bool {
+    match resp {
+        CommandResp::Stored
+        | CommandResp::Deleted
+        | CommandResp::Touched
+        | CommandResp::Counter(..)
+        | CommandResp::NoResponse => true,
+        CommandResp::Meta(resp) => matches!(
+            resp.status(),
+            MetaStatus::Success | MetaStatus::Value | MetaStatus::Noop
+        ),
+        _ => false,
+    }
+}
+
+/// Failures that mean the server is unreachable rather than rejecting the command.
+fn is_node_failure(err: &MementoError) -> bool {
+    matches!(
+        err,
+        MementoError::IoError(..)
+            | MementoError::ConnectionReset
+            | MementoError::Timeout
+            | MementoError::BrokenConnection
+    )
+}
+
+/// Asks the ejected server for its version until it answers or the cluster is dropped.
+async fn probe(
+    health: Weak<Health>,
+    index: usize,
+    server: Server,
+    builder: MementoBuilder,
+    period: Duration,
+    events: broadcast::Sender<NodeEvent>,
+) {
+    // the probe gives up within its period when the connections wait forever.
+    let timeouts = builder.timeouts();
+    let builder = builder
+        .connect_timeout(timeouts.connect_timeout().unwrap_or(period))
+        .read_timeout(timeouts.read_timeout().unwrap_or(period));
+
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+
+    loop {
+        interval.tick().await;
+
+        if health.strong_count() == 0 {
+            return;
+        }
+
+        let answered = match builder
+            .clone()
+            .connect((server.host.as_str(), server.port))
+            .await
+        {
+            Ok(mut memento) => memento.version().await.is_ok(),
+            Err(..) => false,
+        };
+
+        if !answered {
+            continue;
+        }
+
+        let Some(health) = health.upgrade() else {
+            return;
+        };
+
+        health.ejected[index].store(false, Ordering::Release);
+        health.changed.store(true, Ordering::Release);
+
+        let _ = events.send(NodeEvent::Restored(server));
+
+        return;
+    }
+}
+
+/// Server of the cluster with its connection opened on first use.
+#[derive(Debug)]
+struct Node {
+    server: Server,
+    memento: Option<Memento>,
+    /// Consecutive failures, counted only when ejection is enabled.
+    failures: u32,
+    /// Moving average of the time the server takes to reply.
+    latency: Option<Duration>,
+}
+
+impl Node {
+    fn new(server: Server) -> Self {
+        Self {
+            server,
+            memento: None,
+            failures: 0,
+            latency: None,
+        }
+    }
+
+    fn observe(&mut self, elapsed: Duration) {
+        self.latency = Some(match self.latency {
+            Some(latency) => latency.mul_f64(0.8) + elapsed.mul_f64(0.2),
+            None => elapsed,
+        });
+    }
+
+    async fn connect(&mut self, builder: &MementoBuilder) -> crate::Result<&mut Memento> {
+        if self.memento.is_none() {
+            let memento = builder
+                .clone()
+                .connect((self.server.host.as_str(), self.server.port))
+                .await?;
+
+            self.memento = Some(memento);
+        }
+
+        Ok(self.memento.as_mut().expect("connection is just opened"))
+    }
+}
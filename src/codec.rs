@@ -0,0 +1,198 @@
+use crate::{BinaryCodec, Command, Decr, Frame, Incr, MementoError};
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Codec for the memcached text protocol.
+///
+/// Decoding is incremental: frames are taken from the buffer as soon as they are complete,
+/// a data block is returned only when all octets announced by its `VALUE` or `VA` line
+/// and the trailing `\r\n` arrived, and a response is yielded once its last line is seen.
+///
+/// ```rust
+/// use bytes::BytesMut;
+/// use memento::{AsciiCodec, Frame};
+/// use tokio_util::codec::Decoder;
+///
+/// let mut codec = AsciiCodec::default();
+/// let mut buffer = BytesMut::from(&b"VALUE x 0 4\r\na\r\n"[..]);
+///
+/// assert_eq!(None, codec.decode(&mut buffer)?);
+///
+/// buffer.extend_from_slice(b"b\r\nEND\r\n");
+///
+/// assert_eq!(
+///     Some(vec![
+///         Frame::Line("VALUE x 0 4".to_string()),
+///         Frame::Data("a\r\nb".into()),
+///         Frame::Line("END".to_string()),
+///     ]),
+///     codec.decode(&mut buffer)?,
+/// );
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct AsciiCodec {
+    frames: Vec<Frame>,
+}
+
+impl AsciiCodec {
+    /// Responses to retrieval and stats commands consist of many lines terminated by `END`
+    /// or by an error, everything else is a single line.
+    fn is_complete(&self) -> bool {
+        match self.frames.first().and_then(Frame::line) {
+            Some(line) if line.starts_with("VALUE") || line.starts_with("STAT") => self
+                .frames
+                .last()
+                .and_then(Frame::line)
+                .is_some_and(|line| {
+                    line == "END"
+                        || line.starts_with("CLIENT_ERROR")
+                        || line.starts_with("SERVER_ERROR")
+                }),
+            Some(..) => true,
+            None => false,
+        }
+    }
+}
+
+impl Decoder for AsciiCodec {
+    type Item = Vec<Frame>;
+    type Error = MementoError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> crate::Result<Option<Self::Item>> {
+        while let Some(end) = src.windows(2).position(|window| window == b"\r\n") {
+            let line = String::from_utf8_lossy(&src[..end]).to_string();
+
+            let len_position = match line.split_whitespace().next() {
+                Some("VALUE") => Some(3),
+                Some("VA") => Some(1),
+                _ => None,
+            };
+
+            if let Some(len_position) = len_position {
+                let len = line
+                    .split_whitespace()
+                    .nth(len_position)
+                    .ok_or_else(|| MementoError::InvalidResponse(line.clone()))?
+                    .parse::<usize>()?;
+
+                let frame_len = end + 2 + len + 2;
+
+                if src.len() < frame_len {
+                    src.reserve(frame_len - src.len());
+
+                    return Ok(None);
+                }
+
+                if &src[frame_len - 2..frame_len] != b"\r\n" {
+                    return Err(MementoError::InvalidResponse(format!(
+                        "data block of {line} is not terminated by \\r\\n"
+                    )));
+                }
+
+                src.advance(end + 2);
+                self.frames.push(Frame::Line(line));
+                self.frames.push(Frame::Data(src.split_to(len).freeze()));
+                src.advance(2);
+            } else {
+                src.advance(end + 2);
+                self.frames.push(Frame::Line(line));
+            }
+
+            if self.is_complete() {
+                return Ok(Some(std::mem::take(&mut self.frames)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> crate::Result<Option<Self::Item>> {
+        match self.decode(src)? {
+            Some(frames) => Ok(Some(frames)),
+            None if src.is_empty() && self.frames.is_empty() => Ok(None),
+            None => Err(MementoError::ConnectionReset),
+        }
+    }
+}
+
+impl Encoder<&Command> for AsciiCodec {
+    type Error = MementoError;
+
+    fn encode(&mut self, cmd: &Command, dst: &mut BytesMut) -> crate::Result<()> {
+        if let Command::Incr(Incr {
+            initial: Some(..), ..
+        })
+        | Command::Decr(Decr {
+            initial: Some(..), ..
+        }) = cmd
+        {
+            return Err(MementoError::UnsupportedCommand(
+                "initial counter value requires the binary protocol".to_string(),
+            ));
+        }
+
+        cmd.encode(dst);
+
+        Ok(())
+    }
+}
+
+/// Wire protocol spoken with the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Protocol {
+    #[default]
+    Ascii,
+    Binary,
+}
+
+/// Codec of the protocol chosen for the connection.
+#[derive(Debug)]
+pub(crate) enum Codec {
+    Ascii(AsciiCodec),
+    Binary(BinaryCodec),
+}
+
+impl From<Protocol> for Codec {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::Ascii => Self::Ascii(AsciiCodec::default()),
+            Protocol::Binary => Self::Binary(BinaryCodec::default()),
+        }
+    }
+}
+
+impl Decoder for Codec {
+    type Item = Vec<Frame>;
+    type Error = MementoError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> crate::Result<Option<Self::Item>> {
+        match self {
+            Self::Ascii(codec) => codec.decode(src),
+            Self::Binary(codec) => codec.decode(src),
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> crate::Result<Option<Self::Item>> {
+        match self {
+            Self::Ascii(codec) => codec.decode_eof(src),
+            Self::Binary(codec) => codec.decode_eof(src),
+        }
+    }
+}
+
+impl Encoder<&Command> for Codec {
+    type Error = MementoError;
+
+    fn encode(&mut self, cmd: &Command, dst: &mut BytesMut) -> crate::Result<()> {
+        match self {
+            Self::Ascii(codec) => codec.encode(cmd, dst),
+            Self::Binary(codec) => codec.encode(cmd, dst),
+        }
+    }
+}
@@ -1,22 +1,43 @@
-use crate::{MementoError, ToCommandResponse};
-use std::fmt::Debug;
+use crate::{
+    Frame, MementoError, MetaArithmetic, MetaDebug, MetaDelete, MetaGet, MetaResponse, MetaSet,
+    ToCommandResponse,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use std::time::Duration;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Key {
     value: String,
 }
 
-impl ToString for Key {
-    fn to_string(&self) -> String {
-        self.value.to_string()
+impl Key {
+    pub fn as_bytes(&self) -> &[u8] {
+        self.value.as_bytes()
+    }
+
+    /// Key with the prefix in front, still limited to 250 bytes.
+    pub(crate) fn prefixed(&self, prefix: &str) -> crate::Result<Key> {
+        let value = format!("{prefix}{}", self.value);
+
+        if value.len() > 250 {
+            return Err(MementoError::TooLongKey(value));
+        }
+
+        Ok(Key { value })
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
     }
 }
 
 ///
 /// ```rust
-/// async fn main() -> memento::Result<()> {
+/// fn main() -> memento::Result<()> {
 ///     let raw_key = "x".parse::<memento::Key>()?; // x
 ///     let value_key = "VALUE x 0 3".parse::<memento::Key>()?; // x
 ///
@@ -31,8 +52,7 @@ impl FromStr for Key {
             return Ok(Key {
                 value: value
                     .split_whitespace()
-                    .skip(1)
-                    .next()
+                    .nth(1)
                     .unwrap_or_default()
                     .to_string(),
             });
@@ -42,16 +62,18 @@ impl FromStr for Key {
             return Err(MementoError::TooLongKey(value.to_string()));
         }
 
-        return Ok(Key {
+        Ok(Key {
             value: value.to_string(),
-        });
+        })
     }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct Item {
-    value: String,
+    value: Bytes,
     expires: Option<Duration>,
+    flags: u32,
+    cas: Option<u64>,
 }
 
 impl Item {
@@ -64,8 +86,10 @@ impl Item {
     /// ```
     pub fn expires<T: ToString>(value: T, expires: Duration) -> Self {
         Self {
-            value: value.to_string(),
+            value: Bytes::from(value.to_string()),
             expires: Some(expires),
+            flags: 0,
+            cas: None,
         }
     }
 
@@ -77,11 +101,85 @@ impl Item {
     /// ```
     pub fn timeless<T: ToString>(value: T) -> Self {
         Self {
-            value: value.to_string(),
+            value: Bytes::from(value.to_string()),
+            expires: None,
+            flags: 0,
+            cas: None,
+        }
+    }
+
+    /// Creates a timeless item from raw bytes without any text conversion.
+    ///
+    /// ```rust
+    /// use memento::Item;
+    ///
+    /// let item = Item::bytes(vec![0xde, 0xad, b'\r', b'\n', 0xbe, 0xef]);
+    ///
+    /// assert_eq!(6, item.value().len());
+    /// ```
+    pub fn bytes<T: Into<Bytes>>(value: T) -> Self {
+        Self {
+            value: value.into(),
             expires: None,
+            flags: 0,
+            cas: None,
         }
     }
 
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use memento::Item;
+    ///
+    /// let item = Item::bytes(&b"y"[..]).with_expiration(Duration::from_secs(2));
+    /// ```
+    pub fn with_expiration(mut self, expires: Duration) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+
+    /// Opaque client flags stored along with the value, e.g. to mark its encoding.
+    ///
+    /// ```rust
+    /// use memento::Item;
+    ///
+    /// const JSON: u32 = 1;
+    ///
+    /// let item = Item::timeless(r#"{"x":"y"}"#).with_flags(JSON);
+    ///
+    /// assert_eq!(JSON, item.flags());
+    /// ```
+    pub fn with_flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Client flags of the item, `0` unless set explicitly or returned by the server.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Expiration time of the item, `None` for items that never expire.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.expires
+    }
+
+    /// Cas unique of the item, known only for items returned by `gets` and `gats`,
+    /// or by any retrieval command over the binary protocol.
+    pub fn cas(&self) -> Option<u64> {
+        self.cas
+    }
+
+    /// Raw value of the item.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Consumes the item and returns its raw value.
+    pub fn into_bytes(self) -> Bytes {
+        self.value
+    }
+
     fn seconds(&self) -> u64 {
         self.expires.unwrap_or(Duration::from_secs(0)).as_secs()
     }
@@ -89,7 +187,7 @@ impl Item {
 
 ///
 /// ```rust
-/// async fn main() -> memento::Result<()> {
+/// fn main() -> memento::Result<()> {
 ///     let item = "value".parse::<memento::Item>()?; // value
 ///
 ///     Ok(())
@@ -103,16 +201,48 @@ impl FromStr for Item {
     }
 }
 
-impl ToString for Item {
-    fn to_string(&self) -> String {
-        self.value.to_string()
+impl From<Bytes> for Item {
+    fn from(value: Bytes) -> Self {
+        Item::bytes(value)
+    }
+}
+
+impl From<Vec<u8>> for Item {
+    fn from(value: Vec<u8>) -> Self {
+        Item::bytes(value)
+    }
+}
+
+impl From<&[u8]> for Item {
+    fn from(value: &[u8]) -> Self {
+        Item::bytes(Bytes::copy_from_slice(value))
+    }
+}
+
+impl From<&str> for Item {
+    fn from(value: &str) -> Self {
+        Item::bytes(Bytes::copy_from_slice(value.as_bytes()))
+    }
+}
+
+impl From<String> for Item {
+    fn from(value: String) -> Self {
+        Item::bytes(value)
+    }
+}
+
+/// Lossy UTF-8 representation of the value, binary data is not guaranteed to be readable.
+impl Display for Item {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.value))
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Set {
-    key: Key,
-    item: Item,
+    pub(crate) key: Key,
+    pub(crate) item: Item,
+    pub(crate) noreply: bool,
 }
 
 impl Set {
@@ -121,16 +251,99 @@ impl Set {
     /// use memento::{Item, Set};
     ///
     /// let cmd = Set::new("x".parse()?, Item::timeless("y"));
+    /// # Ok::<(), memento::MementoError>(())
     /// ```
     pub fn new(key: Key, item: Item) -> Self {
-        Self { key, item }
+        Self {
+            key,
+            item,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::{Item, Set};
+    ///
+    /// let cmd = Set::new("x".parse()?, Item::timeless("y")).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    ///
+    /// The server still reports the errors of `noreply` commands, so the next command waiting
+    /// for a reply is preceded by `version` and everything up to its reply is skipped.
+    ///
+    /// ```rust
+    /// use memento::{Command, CommandResp, Item, Memento, Set};
+    /// use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let (client, server) = tokio::io::duplex(1024);
+    ///
+    ///     let server = tokio::spawn(async move {
+    ///         let mut stream = BufReader::new(server);
+    ///         let (mut line, mut lines) = (String::new(), Vec::new());
+    ///
+    ///         while lines.len() < 4 && stream.read_line(&mut line).await? > 0 {
+    ///             let reply = match line.split_whitespace().collect::<Vec<_>>()[..] {
+    ///                 ["set", key, _, _, len, "noreply"] => {
+    ///                     let mut data = vec![0; len.parse::<usize>().unwrap_or_default() + 2];
+    ///
+    ///                     stream.read_exact(&mut data).await?;
+    ///
+    ///                     match key {
+    ///                         "big" => "SERVER_ERROR object too large for cache\r\n",
+    ///                         _ => "",
+    ///                     }
+    ///                 }
+    ///                 ["version"] => "VERSION 1.6.21\r\n",
+    ///                 ["get", "x"] => "VALUE x 0 1\r\ny\r\nEND\r\n",
+    ///                 _ => "ERROR\r\n",
+    ///             };
+    ///
+    ///             stream.write_all(reply.as_bytes()).await?;
+    ///             lines.push(line.trim_end().to_string());
+    ///             line.clear();
+    ///         }
+    ///
+    ///         Ok::<_, std::io::Error>(lines)
+    ///     });
+    ///
+    ///     let mut memento = Memento::from_stream(client);
+    ///
+    ///     for (key, value) in [("x", "y"), ("big", "z")] {
+    ///         let cmd = Set::new(key.parse()?, Item::timeless(value)).noreply();
+    ///
+    ///         assert!(matches!(memento.call(Command::Set(cmd)).await?, CommandResp::NoResponse));
+    ///     }
+    ///
+    ///     // the error of the second `set` is not taken for the reply of `get`.
+    ///     let response = memento.get("x".parse()?).await?;
+    ///
+    ///     assert!(matches!(response, CommandResp::Value { item, .. } if item.to_string() == "y"));
+    ///
+    ///     let lines = server.await.expect("server panicked")?;
+    ///
+    ///     assert_eq!(
+    ///         vec!["set x 0 0 1 noreply", "set big 0 0 1 noreply", "version", "get x"],
+    ///         lines
+    ///     );
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Add {
-    key: Key,
-    item: Item,
+    pub(crate) key: Key,
+    pub(crate) item: Item,
+    pub(crate) noreply: bool,
 }
 
 impl Add {
@@ -139,16 +352,35 @@ impl Add {
     /// use memento::{Item, Add};
     ///
     /// let cmd = Add::new("x".parse()?, Item::timeless("y"));
+    /// # Ok::<(), memento::MementoError>(())
     /// ```
     pub fn new(key: Key, item: Item) -> Self {
-        Self { key, item }
+        Self {
+            key,
+            item,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::{Item, Add};
+    ///
+    /// let cmd = Add::new("x".parse()?, Item::timeless("y")).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Append {
-    key: Key,
-    item: Item,
+    pub(crate) key: Key,
+    pub(crate) item: Item,
+    pub(crate) noreply: bool,
 }
 
 impl Append {
@@ -157,16 +389,35 @@ impl Append {
     /// use memento::{Item, Append};
     ///
     /// let cmd = Append::new("x".parse()?, Item::timeless("y"));
+    /// # Ok::<(), memento::MementoError>(())
     /// ```
     pub fn new(key: Key, item: Item) -> Self {
-        Self { key, item }
+        Self {
+            key,
+            item,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::{Item, Append};
+    ///
+    /// let cmd = Append::new("x".parse()?, Item::timeless("y")).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Prepend {
-    key: Key,
-    item: Item,
+    pub(crate) key: Key,
+    pub(crate) item: Item,
+    pub(crate) noreply: bool,
 }
 
 impl Prepend {
@@ -175,16 +426,35 @@ impl Prepend {
     /// use memento::{Item, Prepend};
     ///
     /// let cmd = Prepend::new("x".parse()?, Item::timeless("y"));
+    /// # Ok::<(), memento::MementoError>(())
     /// ```
     pub fn new(key: Key, item: Item) -> Self {
-        Self { key, item }
+        Self {
+            key,
+            item,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::{Item, Prepend};
+    ///
+    /// let cmd = Prepend::new("x".parse()?, Item::timeless("y")).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Replace {
-    key: Key,
-    item: Item,
+    pub(crate) key: Key,
+    pub(crate) item: Item,
+    pub(crate) noreply: bool,
 }
 
 impl Replace {
@@ -193,16 +463,75 @@ impl Replace {
     /// use memento::{Item, Replace};
     ///
     /// let cmd = Replace::new("x".parse()?, Item::timeless("y"));
+    /// # Ok::<(), memento::MementoError>(())
     /// ```
     pub fn new(key: Key, item: Item) -> Self {
-        Self { key, item }
+        Self {
+            key,
+            item,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::{Item, Replace};
+    ///
+    /// let cmd = Replace::new("x".parse()?, Item::timeless("y")).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cas {
+    pub(crate) key: Key,
+    pub(crate) item: Item,
+    pub(crate) cas: u64,
+    pub(crate) noreply: bool,
+}
+
+impl Cas {
+    ///
+    /// ```rust
+    /// use memento::{Item, Cas};
+    ///
+    /// let cmd = Cas::new("x".parse()?, Item::timeless("y"), 42);
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn new(key: Key, item: Item, cas: u64) -> Self {
+        Self {
+            key,
+            item,
+            cas,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::{Item, Cas};
+    ///
+    /// let cmd = Cas::new("x".parse()?, Item::timeless("y"), 42).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Incr {
-    key: Key,
-    value: u64,
+    pub(crate) key: Key,
+    pub(crate) value: u64,
+    pub(crate) initial: Option<u64>,
+    pub(crate) noreply: bool,
 }
 
 impl Incr {
@@ -211,16 +540,51 @@ impl Incr {
     /// use memento::Incr;
     ///
     /// let cmd = Incr::new("x".parse()?, 1);
+    /// # Ok::<(), memento::MementoError>(())
     /// ```
     pub fn new(key: Key, value: u64) -> Self {
-        Self { key, value }
+        Self {
+            key,
+            value,
+            initial: None,
+            noreply: false,
+        }
+    }
+
+    /// Creates the counter with the initial value when the key is missing instead of
+    /// responding with `NotFound`. Supported by the binary protocol only.
+    ///
+    /// ```rust
+    /// use memento::Incr;
+    ///
+    /// let cmd = Incr::new("x".parse()?, 1).initial(100);
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn initial(mut self, initial: u64) -> Self {
+        self.initial = Some(initial);
+        self
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::Incr;
+    ///
+    /// let cmd = Incr::new("x".parse()?, 1).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Decr {
-    key: Key,
-    value: u64,
+    pub(crate) key: Key,
+    pub(crate) value: u64,
+    pub(crate) initial: Option<u64>,
+    pub(crate) noreply: bool,
 }
 
 impl Decr {
@@ -229,9 +593,170 @@ impl Decr {
     /// use memento::Decr;
     ///
     /// let cmd = Decr::new("x".parse()?, 1);
+    /// # Ok::<(), memento::MementoError>(())
     /// ```
     pub fn new(key: Key, value: u64) -> Self {
-        Self { key, value }
+        Self {
+            key,
+            value,
+            initial: None,
+            noreply: false,
+        }
+    }
+
+    /// Creates the counter with the initial value when the key is missing instead of
+    /// responding with `NotFound`. Supported by the binary protocol only.
+    ///
+    /// ```rust
+    /// use memento::Decr;
+    ///
+    /// let cmd = Decr::new("x".parse()?, 1).initial(100);
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn initial(mut self, initial: u64) -> Self {
+        self.initial = Some(initial);
+        self
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::Decr;
+    ///
+    /// let cmd = Decr::new("x".parse()?, 1).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Delete {
+    pub(crate) key: Key,
+    pub(crate) noreply: bool,
+}
+
+impl Delete {
+    ///
+    /// ```rust
+    /// use memento::Delete;
+    ///
+    /// let cmd = Delete::new("x".parse()?);
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn new(key: Key) -> Self {
+        Self {
+            key,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::Delete;
+    ///
+    /// let cmd = Delete::new("x".parse()?).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Touch {
+    pub(crate) key: Key,
+    pub(crate) ttl: Duration,
+    pub(crate) noreply: bool,
+}
+
+impl Touch {
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use memento::Touch;
+    ///
+    /// let cmd = Touch::new("x".parse()?, Duration::from_secs(60));
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn new(key: Key, ttl: Duration) -> Self {
+        Self {
+            key,
+            ttl,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use memento::Touch;
+    ///
+    /// let cmd = Touch::new("x".parse()?, Duration::from_secs(60)).noreply();
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gat {
+    pub(crate) ttl: Duration,
+    pub(crate) keys: Vec<Key>,
+}
+
+impl Gat {
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use memento::Gat;
+    ///
+    /// let cmd = Gat::new(Duration::from_secs(60), vec!["x".parse()?, "y".parse()?]);
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn new(ttl: Duration, keys: Vec<Key>) -> Self {
+        Self { ttl, keys }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FlushAll {
+    pub(crate) delay: Option<Duration>,
+    pub(crate) noreply: bool,
+}
+
+impl FlushAll {
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use memento::FlushAll;
+    ///
+    /// let cmd = FlushAll::new(Some(Duration::from_secs(10)));
+    /// ```
+    pub fn new(delay: Option<Duration>) -> Self {
+        Self {
+            delay,
+            noreply: false,
+        }
+    }
+
+    /// Asks the server not to reply, the command is sent without waiting for the response.
+    ///
+    /// ```rust
+    /// use memento::FlushAll;
+    ///
+    /// let cmd = FlushAll::new(None).noreply();
+    /// ```
+    pub fn noreply(mut self) -> Self {
+        self.noreply = true;
+        self
     }
 }
 
@@ -349,159 +874,338 @@ pub enum Command {
     Append(Append),
     Prepend(Prepend),
     Replace(Replace),
+    Cas(Cas),
     Stats,
     Get(Key),
+    GetMulti(Vec<Key>),
     Gets(Vec<Key>),
     Incr(Incr),
     Decr(Decr),
-    Delete(Key),
+    Delete(Delete),
+    Touch(Touch),
+    Gat(Gat),
+    Gats(Gat),
+    FlushAll(FlushAll),
+    MetaGet(MetaGet),
+    MetaSet(MetaSet),
+    MetaDelete(MetaDelete),
+    MetaArithmetic(MetaArithmetic),
+    MetaNoop,
+    MetaDebug(MetaDebug),
     Version,
     Quit,
 }
 
-impl ToString for Command {
-    fn to_string(&self) -> String {
+impl Command {
+    ///
+    /// ```rust
+    /// use memento::{Command, Item, Set};
+    ///
+    /// let cmd = Command::Set(Set::new("x".parse()?, Item::timeless("y")));
+    ///
+    /// assert_eq!(&b"set x 0 0 1\r\ny\r\n"[..], &cmd.to_bytes()[..]);
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn to_bytes(&self) -> Bytes {
+        let mut dst = BytesMut::new();
+
+        self.encode(&mut dst);
+
+        dst.freeze()
+    }
+
+    /// Whether the server sends no response to the command.
+    pub(crate) fn is_noreply(&self) -> bool {
         match self {
-            Self::Set(cmd) => {
-                format!(
-                    "set {key} {flags} {expires} {len}\r\n{value}\r\n",
-                    key = cmd.key.to_string(),
-                    flags = 0,
-                    expires = cmd.item.seconds(),
-                    len = cmd.item.value.len(),
-                    value = cmd.item.value
-                )
-            }
-            Self::Add(cmd) => {
-                format!(
-                    "add {key} {flags} {expires} {len}\r\n{value}\r\n",
-                    key = cmd.key.to_string(),
-                    flags = 0,
-                    expires = cmd.item.seconds(),
-                    len = cmd.item.value.len(),
-                    value = cmd.item.value
-                )
-            }
-            Self::Append(cmd) => {
-                format!(
-                    "append {key} {flags} {expires} {len}\r\n{value}\r\n",
-                    key = cmd.key.to_string(),
-                    flags = 0,
-                    expires = cmd.item.seconds(),
-                    len = cmd.item.value.len(),
-                    value = cmd.item.value
-                )
-            }
-            Self::Prepend(cmd) => {
-                format!(
-                    "prepend {key} {flags} {expires} {len}\r\n{value}\r\n",
-                    key = cmd.key.to_string(),
-                    flags = 0,
-                    expires = cmd.item.seconds(),
-                    len = cmd.item.value.len(),
-                    value = cmd.item.value
-                )
-            }
-            Self::Replace(cmd) => {
-                format!(
-                    "replace {key} {flags} {expires} {len}\r\n{value}\r\n",
-                    key = cmd.key.to_string(),
-                    flags = 0,
-                    expires = cmd.item.seconds(),
-                    len = cmd.item.value.len(),
-                    value = cmd.item.value
-                )
-            }
-            Self::Get(key) => format!("get {key}\r\n", key = key.to_string()),
-            Self::Gets(cmd) => {
-                format!(
-                    "gets {key}\r\n",
-                    key = cmd
-                        .iter()
-                        .map(ToString::to_string)
-                        .collect::<Vec<String>>()
-                        .join(" ")
-                )
-            }
-            Self::Stats => "stats\r\n".to_string(),
-            Self::Incr(cmd) => {
-                format!(
-                    "incr {key} {value}\r\n",
-                    key = cmd.key.to_string(),
-                    value = cmd.value
-                )
-            }
-            Self::Decr(cmd) => {
-                format!(
-                    "decr {key} {value}\r\n",
-                    key = cmd.key.to_string(),
-                    value = cmd.value
-                )
+            Self::Set(Set { noreply, .. })
+            | Self::Add(Add { noreply, .. })
+            | Self::Append(Append { noreply, .. })
+            | Self::Prepend(Prepend { noreply, .. })
+            | Self::Replace(Replace { noreply, .. })
+            | Self::Cas(Cas { noreply, .. })
+            | Self::Incr(Incr { noreply, .. })
+            | Self::Decr(Decr { noreply, .. })
+            | Self::Delete(Delete { noreply, .. })
+            | Self::Touch(Touch { noreply, .. })
+            | Self::FlushAll(FlushAll { noreply, .. }) => *noreply,
+            _ => false,
+        }
+    }
+
+    /// Commands that can be safely sent again when the connection broke before the reply.
+    pub(crate) fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Self::Get(..)
+                | Self::GetMulti(..)
+                | Self::Gets(..)
+                | Self::Touch(..)
+                | Self::Delete(..)
+        )
+    }
+
+    /// Whether the command is a meta command with the `q` flag, the server omits
+    /// the uninteresting reply of such commands.
+    pub(crate) fn is_quiet(&self) -> bool {
+        match self {
+            Self::MetaGet(cmd) => cmd.is_quiet(),
+            Self::MetaSet(cmd) => cmd.is_quiet(),
+            Self::MetaDelete(cmd) => cmd.is_quiet(),
+            Self::MetaArithmetic(cmd) => cmd.is_quiet(),
+            _ => false,
+        }
+    }
+
+    /// Adds the opaque token echoed back in the reply to a meta command that has no opaque yet,
+    /// returns the token if it was added.
+    pub(crate) fn tag(&mut self, token: String) -> Option<String> {
+        match self {
+            Self::MetaGet(cmd) => cmd.tag(token),
+            Self::MetaSet(cmd) => cmd.tag(token),
+            Self::MetaDelete(cmd) => cmd.tag(token),
+            Self::MetaArithmetic(cmd) => cmd.tag(token),
+            _ => None,
+        }
+    }
+
+    /// Key of the command that operates on a single key.
+    pub(crate) fn key(&self) -> Option<&Key> {
+        match self {
+            Self::Set(Set { key, .. })
+            | Self::Add(Add { key, .. })
+            | Self::Append(Append { key, .. })
+            | Self::Prepend(Prepend { key, .. })
+            | Self::Replace(Replace { key, .. })
+            | Self::Cas(Cas { key, .. })
+            | Self::Incr(Incr { key, .. })
+            | Self::Decr(Decr { key, .. })
+            | Self::Delete(Delete { key, .. })
+            | Self::Touch(Touch { key, .. })
+            | Self::Get(key) => Some(key),
+            Self::MetaGet(cmd) => Some(cmd.key()),
+            Self::MetaSet(cmd) => Some(cmd.key()),
+            Self::MetaDelete(cmd) => Some(cmd.key()),
+            Self::MetaArithmetic(cmd) => Some(cmd.key()),
+            Self::MetaDebug(cmd) => Some(cmd.key()),
+            _ => None,
+        }
+    }
+
+    /// All keys of the command.
+    pub(crate) fn keys(&self) -> Vec<Key> {
+        match self {
+            Self::GetMulti(keys)
+            | Self::Gets(keys)
+            | Self::Gat(Gat { keys, .. })
+            | Self::Gats(Gat { keys, .. }) => keys.clone(),
+            cmd => cmd.key().into_iter().cloned().collect(),
+        }
+    }
+
+    /// Puts the prefix in front of every key of the command.
+    pub(crate) fn prefix(&mut self, prefix: &str) -> crate::Result<()> {
+        match self {
+            Self::Set(Set { key, .. })
+            | Self::Add(Add { key, .. })
+            | Self::Append(Append { key, .. })
+            | Self::Prepend(Prepend { key, .. })
+            | Self::Replace(Replace { key, .. })
+            | Self::Cas(Cas { key, .. })
+            | Self::Incr(Incr { key, .. })
+            | Self::Decr(Decr { key, .. })
+            | Self::Delete(Delete { key, .. })
+            | Self::Touch(Touch { key, .. })
+            | Self::Get(key) => *key = key.prefixed(prefix)?,
+            Self::GetMulti(keys)
+            | Self::Gets(keys)
+            | Self::Gat(Gat { keys, .. })
+            | Self::Gats(Gat { keys, .. }) => {
+                for key in keys {
+                    *key = key.prefixed(prefix)?;
+                }
             }
-            Self::Delete(key) => format!("delete {key}\r\n", key = key.to_string()),
-            Self::Version => "version\r\n".to_string(),
-            Self::Quit => "quit\r\n".to_string(),
+            Self::MetaGet(cmd) => cmd.prefix(prefix)?,
+            Self::MetaSet(cmd) => cmd.prefix(prefix)?,
+            Self::MetaDelete(cmd) => cmd.prefix(prefix)?,
+            Self::MetaArithmetic(cmd) => cmd.prefix(prefix)?,
+            Self::MetaDebug(cmd) => cmd.prefix(prefix)?,
+            Self::Stats | Self::FlushAll(..) | Self::MetaNoop | Self::Version | Self::Quit => {}
+        }
+
+        Ok(())
+    }
+
+    /// Writes the command in the memcached text protocol format to the buffer.
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        match self {
+            Self::Set(cmd) => storage(dst, "set", &cmd.key, &cmd.item, None, cmd.noreply),
+            Self::Add(cmd) => storage(dst, "add", &cmd.key, &cmd.item, None, cmd.noreply),
+            Self::Append(cmd) => storage(dst, "append", &cmd.key, &cmd.item, None, cmd.noreply),
+            Self::Prepend(cmd) => storage(dst, "prepend", &cmd.key, &cmd.item, None, cmd.noreply),
+            Self::Replace(cmd) => storage(dst, "replace", &cmd.key, &cmd.item, None, cmd.noreply),
+            Self::Cas(cmd) => storage(dst, "cas", &cmd.key, &cmd.item, Some(cmd.cas), cmd.noreply),
+            Self::Get(key) => dst.put(format!("get {key}\r\n").as_bytes()),
+            Self::GetMulti(keys) => retrieval(dst, "get", keys),
+            Self::Gets(keys) => retrieval(dst, "gets", keys),
+            Self::Stats => dst.put(&b"stats\r\n"[..]),
+            Self::Incr(cmd) => line(
+                dst,
+                format!("incr {key} {value}", key = cmd.key, value = cmd.value),
+                cmd.noreply,
+            ),
+            Self::Decr(cmd) => line(
+                dst,
+                format!("decr {key} {value}", key = cmd.key, value = cmd.value),
+                cmd.noreply,
+            ),
+            Self::Delete(cmd) => line(dst, format!("delete {key}", key = cmd.key), cmd.noreply),
+            Self::Touch(cmd) => line(
+                dst,
+                format!("touch {key} {ttl}", key = cmd.key, ttl = cmd.ttl.as_secs()),
+                cmd.noreply,
+            ),
+            Self::Gat(cmd) => retrieval(dst, &format!("gat {}", cmd.ttl.as_secs()), &cmd.keys),
+            Self::Gats(cmd) => retrieval(dst, &format!("gats {}", cmd.ttl.as_secs()), &cmd.keys),
+            Self::FlushAll(cmd) => line(
+                dst,
+                match cmd.delay {
+                    Some(delay) => format!("flush_all {}", delay.as_secs()),
+                    None => "flush_all".to_string(),
+                },
+                cmd.noreply,
+            ),
+            Self::MetaGet(cmd) => cmd.encode(dst),
+            Self::MetaSet(cmd) => cmd.encode(dst),
+            Self::MetaDelete(cmd) => cmd.encode(dst),
+            Self::MetaArithmetic(cmd) => cmd.encode(dst),
+            Self::MetaNoop => dst.put(&b"mn\r\n"[..]),
+            Self::MetaDebug(cmd) => cmd.encode(dst),
+            Self::Version => dst.put(&b"version\r\n"[..]),
+            Self::Quit => dst.put(&b"quit\r\n"[..]),
         }
     }
 }
 
-#[derive(Debug)]
+fn retrieval(dst: &mut BytesMut, name: &str, keys: &[Key]) {
+    dst.put(
+        format!(
+            "{name} {keys}\r\n",
+            keys = keys
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+        .as_bytes(),
+    );
+}
+
+/// Writes the command line, optionally followed by `noreply`, and the `\r\n` terminator.
+fn line(dst: &mut BytesMut, line: String, noreply: bool) {
+    dst.put(line.as_bytes());
+
+    if noreply {
+        dst.put(&b" noreply"[..]);
+    }
+
+    dst.put(&b"\r\n"[..]);
+}
+
+fn storage(
+    dst: &mut BytesMut,
+    name: &str,
+    key: &Key,
+    item: &Item,
+    cas: Option<u64>,
+    noreply: bool,
+) {
+    let mut header = format!(
+        "{name} {key} {flags} {expires} {len}",
+        flags = item.flags,
+        expires = item.seconds(),
+        len = item.value.len(),
+    );
+
+    if let Some(cas) = cas {
+        header.push_str(&format!(" {cas}"));
+    }
+
+    line(dst, header, noreply);
+    dst.put(item.value.clone());
+    dst.put(&b"\r\n"[..]);
+}
+
+#[derive(Debug, Default)]
 pub enum CommandResp {
     Stored,
     Error,
     NotStored,
     Exists,
     NotFound,
+    #[default]
     NoResponse,
     Values(Vec<(Key, Item)>),
-    Value { key: Key, item: Item },
+    Value {
+        key: Key,
+        item: Item,
+    },
     Stats(Vec<Stat>),
     Counter(u64),
     Deleted,
     Touched,
+    Ok,
     Version(String),
-}
-
-impl Default for CommandResp {
-    fn default() -> Self {
-        Self::NoResponse
-    }
+    Meta(MetaResponse),
 }
 
 impl ToCommandResponse for CommandResp {
-    fn create<T>(mut frames: Vec<T>, cmd: Command) -> crate::Result<Option<Self>>
-    where
-        T: ToString + Debug + Default,
-    {
-        let response = match frames
+    fn create(mut frames: Vec<Frame>, cmd: Command) -> crate::Result<Option<Self>> {
+        if let Some(line) = frames.last().and_then(Frame::line) {
+            if line.starts_with("CLIENT_ERROR") {
+                return Err(MementoError::ClientError(message(line)));
+            }
+
+            if line.starts_with("SERVER_ERROR") {
+                return Err(MementoError::ServerError(message(line)));
+            }
+
+            if line.starts_with("AUTH_ERROR") {
+                return Err(MementoError::AuthenticationFailed(message(line)));
+            }
+        }
+
+        let first = frames
             .first()
-            .map(ToString::to_string)
-            .unwrap_or_default()
-            .split_whitespace()
-            .next()
+            .and_then(Frame::line)
             .unwrap_or_default()
-        {
+            .to_string();
+
+        let response = match first.split_whitespace().next().unwrap_or_default() {
             "STORED" => Some(CommandResp::Stored),
+            "VALUE" | "STAT" if !frames.last().is_some_and(Frame::is_end) => None,
             "VALUE" => {
                 frames.pop(); // remove END keyword.
 
-                match cmd {
-                    Command::Get(..) => Some(CommandResp::Value {
-                        key: frames[0].to_string().as_str().parse()?,
-                        item: frames[1].to_string().as_str().parse()?,
-                    }),
-                    Command::Gets(..) => {
-                        let mut values = Vec::default();
-
-                        for chunk in frames.chunks(2) {
-                            values.push((
-                                chunk[0].to_string().as_str().parse::<Key>()?,
-                                chunk[1].to_string().as_str().parse::<Item>()?,
-                            ));
-                        }
+                let mut values = Vec::default();
 
-                        Some(CommandResp::Values(values))
+                for chunk in frames.chunks(2) {
+                    match chunk {
+                        [Frame::Line(header), Frame::Data(data)] => {
+                            values.push(value(header, data.clone())?)
+                        }
+                        _ => return Err(MementoError::InvalidItem(format!("{:?}", chunk))),
                     }
+                }
+
+                match cmd {
+                    Command::Get(..) => values
+                        .into_iter()
+                        .next()
+                        .map(|(key, item)| CommandResp::Value { key, item }),
+                    Command::GetMulti(..)
+                    | Command::Gets(..)
+                    | Command::Gat(..)
+                    | Command::Gats(..) => Some(CommandResp::Values(values)),
                     _ => None,
                 }
             }
@@ -510,8 +1214,8 @@ impl ToCommandResponse for CommandResp {
 
                 let mut stats = Vec::default();
 
-                for stat in frames {
-                    stats.push(stat.to_string().as_str().parse::<Stat>()?);
+                for stat in frames.iter().filter_map(Frame::line) {
+                    stats.push(stat.parse::<Stat>()?);
                 }
 
                 Some(CommandResp::Stats(stats))
@@ -523,11 +1227,12 @@ impl ToCommandResponse for CommandResp {
             "END" => Some(CommandResp::NotFound),
             "EXISTS" => Some(CommandResp::Exists),
             "TOUCHED" => Some(CommandResp::Touched),
+            "OK" => Some(CommandResp::Ok),
+            "HD" | "VA" | "EN" | "NF" | "NS" | "EX" | "MN" | "ME" => {
+                Some(CommandResp::Meta(MetaResponse::parse(&frames)?))
+            }
             "VERSION" => Some(CommandResp::Version(
-                frames
-                    .first()
-                    .map(ToString::to_string)
-                    .unwrap_or_default()
+                first
                     .split_whitespace()
                     .last()
                     .unwrap_or_default()
@@ -545,3 +1250,27 @@ impl ToCommandResponse for CommandResp {
         Ok(response)
     }
 }
+
+/// Message of the `CLIENT_ERROR <message>`, `SERVER_ERROR <message>` and `AUTH_ERROR <message>` lines.
+fn message(line: &str) -> String {
+    line.split_once(' ')
+        .map(|(_, message)| message)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Builds the key and the item from `VALUE <key> <flags> <bytes> [<cas unique>]` line and its data block.
+fn value(header: &str, data: Bytes) -> crate::Result<(Key, Item)> {
+    let mut fields = header.split_whitespace().skip(2);
+
+    let flags = fields
+        .next()
+        .ok_or_else(|| MementoError::InvalidItem(header.to_string()))?
+        .parse::<u32>()?;
+
+    let mut item = Item::bytes(data).with_flags(flags);
+
+    item.cas = fields.nth(1).map(str::parse::<u64>).transpose()?;
+
+    Ok((header.parse::<Key>()?, item))
+}
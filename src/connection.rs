@@ -1,81 +1,624 @@
-use crate::{Command, MementoError, ToCommandResponse};
-use bytes::{Buf, BytesMut};
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
-use tokio::net::{TcpStream, ToSocketAddrs};
+use crate::binary::{sasl_auth, sasl_list_mechs};
+use crate::codec::Codec;
+use crate::sasl::PLAIN;
+use crate::timeout::timeout;
+#[cfg(feature = "tls")]
+use crate::tls::Connector;
+#[cfg(feature = "tls")]
+use crate::TlsConfig;
+use crate::{
+    Backoff, Command, Credentials, Frame, MementoError, Protocol, Timeouts, ToCommandResponse,
+};
+use bytes::BytesMut;
+use socket2::{SockRef, TcpKeepalive};
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufStream};
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::net::{lookup_host, TcpStream, ToSocketAddrs};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Byte stream the connection runs on.
+pub(crate) trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Settings of the connection, kept for reconnects.
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    pub(crate) protocol: Protocol,
+    pub(crate) credentials: Option<Credentials>,
+    pub(crate) timeouts: Timeouts,
+    pub(crate) backoff: Option<Backoff>,
+    pub(crate) retry: bool,
+    pub(crate) read_buffer_size: usize,
+    pub(crate) write_buffer_size: usize,
+    pub(crate) nodelay: bool,
+    pub(crate) keepalive: Option<Duration>,
+    pub(crate) key_prefix: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            protocol: Protocol::default(),
+            credentials: None,
+            timeouts: Timeouts::default(),
+            backoff: Some(Backoff::default()),
+            retry: false,
+            read_buffer_size: 8 * 1024,
+            write_buffer_size: 8 * 1024,
+            nodelay: false,
+            keepalive: None,
+            key_prefix: None,
+        }
+    }
+}
+
+impl Options {
+    /// Prefix put in front of every key, empty when there is none.
+    pub(crate) fn key_prefix(&self) -> &str {
+        self.key_prefix.as_deref().unwrap_or_default()
+    }
+}
+
+/// Where the stream is opened again after the connection broke.
+#[derive(Debug, Clone)]
+enum Endpoint {
+    Tcp(Vec<SocketAddr>),
+    #[cfg(unix)]
+    Unix(PathBuf),
+    #[cfg(feature = "tls")]
+    Tls(Vec<SocketAddr>, Connector),
+}
+
+impl Endpoint {
+    async fn connect(&self, options: &Options) -> crate::Result<Box<dyn Stream>> {
+        match self {
+            Self::Tcp(addrs) => Ok(Box::new(tcp(addrs, options).await?)),
+            #[cfg(unix)]
+            Self::Unix(path) => Ok(Box::new(UnixStream::connect(path).await?)),
+            #[cfg(feature = "tls")]
+            Self::Tls(addrs, connector) => {
+                let stream = tcp(addrs, options).await?;
+
+                Ok(Box::new(connector.connect(stream).await?))
+            }
+        }
+    }
+}
+
+/// Connects to the first address that accepts the connection and applies the socket options.
+async fn tcp(addrs: &[SocketAddr], options: &Options) -> crate::Result<TcpStream> {
+    let stream = TcpStream::connect(addrs).await?;
+
+    stream.set_nodelay(options.nodelay)?;
+
+    if let Some(time) = options.keepalive {
+        SockRef::from(&stream).set_tcp_keepalive(&TcpKeepalive::new().with_time(time))?;
+    }
+
+    Ok(stream)
+}
 
-#[derive(Debug)]
 pub(crate) struct Connection {
-    stream: BufStream<TcpStream>,
+    stream: BufStream<Box<dyn Stream>>,
     buffer: BytesMut,
-    cursor: usize,
+    codec: Codec,
+    unsynced: bool,
+    broken: bool,
+    endpoint: Option<Endpoint>,
+    options: Options,
+    next_timeouts: Option<Timeouts>,
 }
 
-unsafe impl Send for Connection {}
+impl Debug for Connection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Connection")
+            .field("endpoint", &self.endpoint)
+            .field("protocol", &self.options.protocol)
+            .field("broken", &self.broken)
+            .finish_non_exhaustive()
+    }
+}
 
 impl Connection {
-    /// Connection used by Memento to handle read/write operations.
-    /// Uses BufStream with 4KB capacity by default.
-    pub(crate) fn from_stream(stream: TcpStream) -> Self {
+    /// Connection used by Memento to handle read/write operations over any stream,
+    /// such connection cannot be reopened since its endpoint is unknown.
+    pub(crate) fn from_stream<S: Stream + 'static>(stream: S, options: Options) -> Self {
+        Self::new(Box::new(stream), options)
+    }
+
+    fn new(stream: Box<dyn Stream>, options: Options) -> Self {
         Self {
-            stream: BufStream::new(stream),
-            buffer: BytesMut::with_capacity(4096),
-            cursor: 0,
+            stream: BufStream::with_capacity(
+                options.read_buffer_size,
+                options.write_buffer_size,
+                stream,
+            ),
+            buffer: BytesMut::with_capacity(options.read_buffer_size),
+            codec: Codec::from(options.protocol),
+            unsynced: false,
+            broken: false,
+            endpoint: None,
+            options,
+            next_timeouts: None,
         }
     }
 
-    /// Connect to TcpStream using underlying address that satisfy ToSocketAddrs trait.
-    pub(crate) async fn connect<A: ToSocketAddrs>(addr: A) -> crate::Result<Self> {
-        Ok(Self::from_stream(TcpStream::connect(addr).await?))
+    /// Connect to TcpStream using underlying address that satisfy ToSocketAddrs trait
+    /// and authenticate when credentials are given. Resolved addresses are kept for reconnects.
+    pub(crate) async fn connect<A: ToSocketAddrs>(
+        addr: A,
+        options: Options,
+    ) -> crate::Result<Self> {
+        timeout(options.timeouts.connect_timeout(), async {
+            let endpoint = Endpoint::Tcp(lookup_host(addr).await?.collect());
+
+            Self::establish(endpoint, options).await
+        })
+        .await
     }
 
-    pub(crate) async fn execute<T: ToCommandResponse>(&mut self, cmd: Command) -> crate::Result<T> {
-        self.stream.write_all(cmd.to_string().as_bytes()).await?;
-        self.stream.flush().await?;
+    /// Connect to the Unix domain socket and authenticate when credentials are given.
+    #[cfg(unix)]
+    pub(crate) async fn connect_unix(path: PathBuf, options: Options) -> crate::Result<Self> {
+        timeout(
+            options.timeouts.connect_timeout(),
+            Self::establish(Endpoint::Unix(path), options),
+        )
+        .await
+    }
+
+    /// Connect over TLS, the handshake is repeated on every reconnect. The certificate is
+    /// verified against `host` unless the config names the server.
+    #[cfg(feature = "tls")]
+    pub(crate) async fn connect_tls<A: ToSocketAddrs>(
+        addr: A,
+        host: Option<&str>,
+        tls: &TlsConfig,
+        options: Options,
+    ) -> crate::Result<Self> {
+        let connector = tls.connector(host)?;
+
+        timeout(options.timeouts.connect_timeout(), async {
+            let endpoint = Endpoint::Tls(lookup_host(addr).await?.collect(), connector);
+
+            Self::establish(endpoint, options).await
+        })
+        .await
+    }
+
+    async fn establish(endpoint: Endpoint, options: Options) -> crate::Result<Self> {
+        let mut connection = Self::new(endpoint.connect(&options).await?, options);
+
+        connection.endpoint = Some(endpoint);
+        connection.authenticate().await?;
+
+        Ok(connection)
+    }
+
+    /// Timeouts of the next call only, the ones that are not set fall back to the defaults.
+    pub(crate) fn set_next_timeouts(&mut self, timeouts: Timeouts) {
+        self.next_timeouts = Some(timeouts.or(self.options.timeouts));
+    }
+
+    /// Replaces the stream with a new one to the same server, the new stream is
+    /// authenticated with the credentials of the original one.
+    pub(crate) async fn reconnect(&mut self) -> crate::Result<()> {
+        let timeouts = self.next_timeouts.take().unwrap_or(self.options.timeouts);
 
-        self.read_response(cmd).await
+        self.open(timeouts).await
     }
 
-    async fn read_response<T: ToCommandResponse>(&mut self, cmd: Command) -> crate::Result<T> {
+    async fn open(&mut self, timeouts: Timeouts) -> crate::Result<()> {
+        let Some(endpoint) = self.endpoint.clone() else {
+            return Err(MementoError::IoError(
+                "connection made from a stream cannot be reopened".to_string(),
+            ));
+        };
+
+        self.broken = true;
+
+        timeout(timeouts.connect_timeout(), async {
+            self.stream = BufStream::with_capacity(
+                self.options.read_buffer_size,
+                self.options.write_buffer_size,
+                endpoint.connect(&self.options).await?,
+            );
+            self.buffer.clear();
+            self.codec = Codec::from(self.options.protocol);
+            self.unsynced = false;
+
+            self.authenticate().await
+        })
+        .await?;
+
+        self.broken = false;
+
+        Ok(())
+    }
+
+    /// Reopens a broken connection, waiting between the attempts as the backoff says.
+    /// Rejected credentials and certificates are not retried, neither are connections
+    /// made from a stream since there is nothing to reopen.
+    async fn recover(&mut self, timeouts: Timeouts) -> crate::Result<()> {
+        let (Some(backoff), Some(..)) = (self.options.backoff, &self.endpoint) else {
+            return Err(MementoError::BrokenConnection);
+        };
+
+        let mut attempt = 0;
+
         loop {
-            if let Some(resp) = self.parse_response(cmd.clone()).await? {
-                return Ok(resp);
+            tokio::time::sleep(backoff.delay(attempt)).await;
+
+            match self.open(timeouts).await {
+                Ok(()) => return Ok(()),
+                Err(
+                    err @ (MementoError::AuthenticationFailed(..) | MementoError::TlsError(..)),
+                ) => return Err(err),
+                Err(err) if attempt + 1 >= backoff.max_attempts() => return Err(err),
+                Err(..) => attempt += 1,
             }
+        }
+    }
+
+    /// SASL `PLAIN` exchange: the mechanism is checked against the list announced by the server,
+    /// then `auth` is sent and followed by `step` for as long as the server asks to continue.
+    async fn authenticate(&mut self) -> crate::Result<()> {
+        let Some(credentials) = self.options.credentials.clone() else {
+            return Ok(());
+        };
+
+        if self.options.protocol != Protocol::Binary {
+            return Err(MementoError::UnsupportedCommand(
+                "SASL authentication requires the binary protocol".to_string(),
+            ));
+        }
 
-            if self.buffer.len() == self.cursor {
-                self.buffer.resize(self.cursor * 2, 0);
+        let mut dst = BytesMut::new();
+
+        sasl_list_mechs(&mut dst);
+
+        let line = self.sasl(&dst).await?;
+
+        match line.strip_prefix("SASL_MECHS ") {
+            Some(mechs) if mechs.split_whitespace().any(|mech| mech == PLAIN) => {}
+            Some(mechs) => {
+                return Err(MementoError::AuthenticationFailed(format!(
+                    "server does not support {PLAIN}, available mechanisms: {mechs}"
+                )))
             }
+            None => return Err(sasl_error(line)),
+        }
+
+        let mut step = false;
+
+        loop {
+            dst.clear();
+            sasl_auth(&mut dst, &credentials, step)?;
+
+            let line = self.sasl(&dst).await?;
+
+            match line.as_str() {
+                "AUTHENTICATED" => return Ok(()),
+                line if line.starts_with("AUTH_CONTINUE") => step = true,
+                _ => return Err(sasl_error(line)),
+            }
+        }
+    }
+
+    async fn sasl(&mut self, dst: &[u8]) -> crate::Result<String> {
+        self.stream.write_all(dst).await?;
+        self.stream.flush().await?;
+
+        let frames = self
+            .read_frames()
+            .await?
+            .ok_or(MementoError::ConnectionReset)?;
+
+        match frames.first().and_then(Frame::line) {
+            Some(line) => Ok(line.to_string()),
+            None => Err(MementoError::InvalidResponse(format!("{:?}", frames))),
+        }
+    }
+
+    pub(crate) async fn execute<T: ToCommandResponse>(&mut self, cmd: Command) -> crate::Result<T> {
+        let timeouts = self.take_timeouts().await?;
+        let retry = (self.options.retry && cmd.is_idempotent()).then(|| cmd.clone());
+
+        match (self.round_trip(cmd, timeouts).await, retry) {
+            (Err(MementoError::IoError(..) | MementoError::ConnectionReset), Some(cmd)) => {
+                self.recover(timeouts).await?;
+                self.round_trip(cmd, timeouts).await
+            }
+            (response, ..) => response,
+        }
+    }
+
+    async fn round_trip<T: ToCommandResponse>(
+        &mut self,
+        cmd: Command,
+        timeouts: Timeouts,
+    ) -> crate::Result<T> {
+        let mut dst = BytesMut::new();
+
+        let sync = self.encode(&cmd, &mut dst)?;
+
+        self.write(&dst, timeouts).await?;
+        self.read(cmd, sync, timeouts).await
+    }
+
+    /// Writes all commands with a single flush and reads the replies in order. A failed command
+    /// does not affect the others unless the stream itself failed, then the rest of the
+    /// commands get the same error.
+    pub(crate) async fn execute_many<T: ToCommandResponse>(
+        &mut self,
+        cmds: Vec<Command>,
+    ) -> Vec<crate::Result<T>> {
+        let timeouts = match self.take_timeouts().await {
+            Ok(timeouts) => timeouts,
+            Err(err) => return cmds.iter().map(|_| Err(err.clone())).collect(),
+        };
+
+        let mut dst = BytesMut::new();
+
+        let syncs = cmds
+            .iter()
+            .map(|cmd| self.encode(cmd, &mut dst))
+            .collect::<Vec<_>>();
+
+        if let Err(err) = self.write(&dst, timeouts).await {
+            return cmds.iter().map(|_| Err(err.clone())).collect();
+        }
+
+        let mut responses = Vec::with_capacity(cmds.len());
+        let mut failure: Option<MementoError> = None;
+        let mut broken = false;
 
-            let len = self.stream.read_buf(&mut self.buffer).await?;
+        for (cmd, sync) in cmds.into_iter().zip(syncs) {
+            let response = match (sync, &failure) {
+                (Err(err), _) => Err(err),
+                (Ok(..), Some(err)) => Err(err.clone()),
+                (Ok(sync), None) => {
+                    let response = self.read(cmd, sync, timeouts).await;
 
-            if 0 == len {
-                if self.cursor == 0 {
-                    return Ok(T::default());
+                    if let (Err(err), true) = (&response, self.broken) {
+                        failure = Some(err.clone());
+                    }
+
+                    // a `quit` reads the end of the stream and still succeeds.
+                    broken |= self.broken;
+
+                    response
                 }
+            };
+
+            responses.push(response);
+        }
+
+        // commands that failed to encode were never written, so nothing is left unread.
+        self.broken = broken;
 
-                return Err(MementoError::ConnectionReset);
+        responses
+    }
+
+    /// Timeouts of the call. A connection left in the middle of a request by an error, a timeout
+    /// or a cancelled call cannot be used anymore since the rest of the reply may still be
+    /// on the way, so it is reopened first.
+    async fn take_timeouts(&mut self) -> crate::Result<Timeouts> {
+        let timeouts = self.next_timeouts.take().unwrap_or(self.options.timeouts);
+
+        if self.broken {
+            self.recover(timeouts).await?;
+        }
+
+        Ok(timeouts)
+    }
+
+    /// Encodes the command and returns whether the `version` barrier was put in front of it.
+    /// Nothing is written to `dst` when the command cannot be encoded.
+    fn encode(&mut self, cmd: &Command, dst: &mut BytesMut) -> crate::Result<bool> {
+        let mut buffer = BytesMut::new();
+
+        let sync = self.unsynced && !cmd.is_noreply();
+
+        if sync {
+            self.codec.encode(&Command::Version, &mut buffer)?;
+        }
+
+        match &self.options.key_prefix {
+            Some(prefix) => {
+                let mut cmd = cmd.clone();
+
+                cmd.prefix(prefix)?;
+                self.codec.encode(&cmd, &mut buffer)?;
             }
+            None => self.codec.encode(cmd, &mut buffer)?,
+        }
 
-            self.cursor += len;
+        if cmd.is_quiet() {
+            self.codec.encode(&Command::MetaNoop, &mut buffer)?;
         }
+
+        // a barrier syncs everything written before it.
+        self.unsynced = cmd.is_noreply();
+        dst.unsplit(buffer);
+
+        Ok(sync)
     }
 
-    async fn parse_response<T: ToCommandResponse>(
+    async fn write(&mut self, dst: &[u8], timeouts: Timeouts) -> crate::Result<()> {
+        self.broken = true;
+
+        timeout(timeouts.write_timeout(), async {
+            self.stream.write_all(dst).await?;
+            self.stream.flush().await?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    /// Reads the reply to an already written command, skipping everything
+    /// in front of the barrier when `sync` is set.
+    async fn read<T: ToCommandResponse>(
         &mut self,
         cmd: Command,
-    ) -> crate::Result<Option<T>> {
-        let mut frames: Vec<String> = Vec::new();
+        sync: bool,
+        timeouts: Timeouts,
+    ) -> crate::Result<T> {
+        self.broken = true;
+
+        if cmd.is_noreply() {
+            self.broken = false;
+
+            return Ok(T::default());
+        }
+
+        let frames = timeout(timeouts.read_timeout(), async {
+            if sync {
+                self.sync().await?;
+            }
+
+            match cmd.is_quiet() {
+                true => Ok(Some(self.read_quiet().await?)),
+                false => self.read_frames().await,
+            }
+        })
+        .await?;
+
+        // the response is read in full, so only a closed connection cannot be reused.
+        self.broken = frames.is_none();
 
-        let mut lines = self.buffer.lines();
+        match (frames, cmd) {
+            (Some(mut frames), cmd) => {
+                if let Some(prefix) = &self.options.key_prefix {
+                    unprefix(&mut frames, prefix);
+                }
+
+                Self::create(frames, cmd)
+            }
+            (None, Command::Quit) => Ok(T::default()),
+            (None, ..) => Err(MementoError::ConnectionReset),
+        }
+    }
 
-        let mut frame_len = 0;
+    pub(crate) fn key_prefix(&self) -> &str {
+        self.options.key_prefix()
+    }
 
-        while let Some(line) = lines.next_line().await? {
-            frame_len += line.len() + 2;
-            frames.push(line);
+    /// Whether the connection stopped in the middle of a request or was closed by the server,
+    /// the stream of such connection cannot be used for the next command.
+    pub(crate) fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Quiet meta commands are followed by `mn`, so the `MN` reply coming first means
+    /// that the reply to the command was suppressed.
+    async fn read_quiet(&mut self) -> crate::Result<Vec<Frame>> {
+        let frames = self
+            .read_frames()
+            .await?
+            .ok_or(MementoError::ConnectionReset)?;
+
+        if !frames.first().is_some_and(is_noop) {
+            let noop = self
+                .read_frames()
+                .await?
+                .ok_or(MementoError::ConnectionReset)?;
+
+            if !noop.first().is_some_and(is_noop) {
+                return Err(MementoError::InvalidResponse(format!("{:?}", noop)));
+            }
         }
 
-        self.buffer.advance(frame_len);
+        Ok(frames)
+    }
+
+    /// The server still answers `noreply` commands that failed, so after them a `version`
+    /// command is sent as a barrier and everything up to its response is skipped.
+    async fn sync(&mut self) -> crate::Result<()> {
+        loop {
+            let frames = self
+                .read_frames()
+                .await?
+                .ok_or(MementoError::ConnectionReset)?;
 
-        T::create(frames, cmd)
+            if let Some(line) = frames.first().and_then(Frame::line) {
+                if line.starts_with("VERSION") {
+                    return Ok(());
+                }
+            }
+        }
     }
+
+    /// Reads frames of the next response, `None` means the server closed the connection.
+    async fn read_frames(&mut self) -> crate::Result<Option<Vec<Frame>>> {
+        loop {
+            if let Some(frames) = self.codec.decode(&mut self.buffer)? {
+                return Ok(Some(frames));
+            }
+
+            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+                return self.codec.decode_eof(&mut self.buffer);
+            }
+        }
+    }
+
+    fn create<T: ToCommandResponse>(frames: Vec<Frame>, cmd: Command) -> crate::Result<T> {
+        let debug = format!("{:?}", frames);
+
+        T::create(frames, cmd)?.ok_or(MementoError::InvalidResponse(debug))
+    }
+}
+
+fn sasl_error(line: String) -> MementoError {
+    match line.strip_prefix("AUTH_ERROR") {
+        Some(message) => MementoError::AuthenticationFailed(message.trim().to_string()),
+        None => MementoError::InvalidResponse(line),
+    }
+}
+
+/// Removes the key prefix from the keys the server echoes: `VALUE` and `ME` lines
+/// and the `k` flag of meta replies.
+fn unprefix(frames: &mut [Frame], prefix: &str) {
+    for frame in frames {
+        let Frame::Line(line) = frame else {
+            continue;
+        };
+
+        let mut tokens = line.split(' ').map(str::to_string).collect::<Vec<_>>();
+
+        match tokens[0].as_str() {
+            "VALUE" | "ME" => {
+                if let Some(token) = tokens.get_mut(1) {
+                    if let Some(key) = token.strip_prefix(prefix) {
+                        *token = key.to_string();
+                    }
+                }
+            }
+            "VA" | "HD" | "EN" | "NF" | "NS" | "EX" => {
+                for token in tokens.iter_mut().skip(1) {
+                    if let Some(key) = token
+                        .strip_prefix('k')
+                        .and_then(|key| key.strip_prefix(prefix))
+                    {
+                        *token = format!("k{key}");
+                    }
+                }
+            }
+            _ => continue,
+        }
+
+        *line = tokens.join(" ");
+    }
+}
+
+fn is_noop(frame: &Frame) -> bool {
+    frame.line() == Some("MN")
 }
@@ -0,0 +1,338 @@
+use crate::{Key, Server};
+use std::fmt::Debug;
+
+/// Maps keys to the servers of `MementoCluster`.
+///
+/// `update` is called with the servers before the first key is placed, `server` then returns
+/// the index of the server among them.
+///
+/// ```rust
+/// use memento::{Distribution, Key, MementoCluster, Server};
+///
+/// /// Everything goes to the first server.
+/// #[derive(Debug)]
+/// struct First;
+///
+/// impl Distribution for First {
+///     fn update(&mut self, _servers: &[Server]) {}
+///
+///     fn server(&self, _key: &Key) -> usize {
+///         0
+///     }
+/// }
+///
+/// let cluster = MementoCluster::builder()
+///     .server("10.0.1.1:11211".parse()?)
+///     .server("10.0.1.2:11211".parse()?)
+///     .distribution(First)
+///     .build()?;
+///
+/// assert_eq!("10.0.1.1:11211", cluster.server(&"x".parse()?).to_string());
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+pub trait Distribution: Debug + Send + Sync {
+    /// Places the servers, the list is never empty.
+    fn update(&mut self, servers: &[Server]);
+
+    /// Index of the server the key belongs to, less than the number of servers.
+    fn server(&self, key: &Key) -> usize;
+
+    /// Indexes of up to `count` distinct servers holding the replicas of the key, starting with
+    /// `server`. When fewer are returned, as by default, the servers following the first one
+    /// in the list hold the rest of the replicas.
+    fn replicas(&self, key: &Key, _count: usize) -> Vec<usize> {
+        vec![self.server(key)]
+    }
+}
+
+/// Hash of the key used to pick the server, the values match the hash functions of libmemcached.
+///
+/// ```rust
+/// use memento::HashFunction;
+///
+/// assert_eq!(0x3610, HashFunction::Crc32.hash(b"hello"));
+/// assert_eq!(0x4f9f2cab, HashFunction::Fnv1a.hash(b"hello"));
+/// assert_eq!(0x2a40415d, HashFunction::Md5.hash(b"hello"));
+/// assert_eq!(0x248bfa47, HashFunction::Murmur3.hash(b"hello"));
+///
+/// // published test vectors of Jenkins' one-at-a-time hash
+/// assert_eq!(0xca2e9442, HashFunction::OneAtATime.hash(b"a"));
+/// assert_eq!(0x519e91f5, HashFunction::OneAtATime.hash(b"The quick brown fox jumps over the lazy dog"));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashFunction {
+    /// Bits 16 to 30 of the CRC-32, so the hash is at most `0x7fff`.
+    Crc32,
+    /// 32-bit FNV-1a.
+    Fnv1a,
+    /// First four bytes of the MD5 digest as a little-endian number.
+    #[default]
+    Md5,
+    /// 32-bit x86 MurmurHash3 with zero seed.
+    Murmur3,
+    /// Jenkins' one-at-a-time hash, the default of libmemcached. Bytes above `0x7f` are sign
+    /// extended as libmemcached does.
+    OneAtATime,
+}
+
+impl HashFunction {
+    pub fn hash(&self, key: &[u8]) -> u32 {
+        match self {
+            Self::Crc32 => (crc32fast::hash(key) >> 16) & 0x7fff,
+            Self::Fnv1a => key.iter().fold(0x811c9dc5, |hash, byte| {
+                (hash ^ *byte as u32).wrapping_mul(0x01000193)
+            }),
+            Self::Md5 => {
+                let digest = md5::compute(key);
+
+                u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]])
+            }
+            Self::Murmur3 => murmur3::murmur3_32(&mut &key[..], 0).expect("slice reads never fail"),
+            Self::OneAtATime => {
+                let hash = key.iter().fold(0u32, |hash, byte| {
+                    let hash = hash.wrapping_add(*byte as i8 as u32);
+                    let hash = hash.wrapping_add(hash << 10);
+
+                    hash ^ (hash >> 6)
+                });
+                let hash = hash.wrapping_add(hash << 3);
+                let hash = hash ^ (hash >> 11);
+
+                hash.wrapping_add(hash << 15)
+            }
+        }
+    }
+
+    /// Number of distinct hashes.
+    fn range(&self) -> f64 {
+        match self {
+            Self::Crc32 => 32768.0,
+            _ => 4294967296.0,
+        }
+    }
+}
+
+/// Hash of the key modulo the total weight, a server of weight 2 takes two buckets in a row.
+/// With equal weights this is `hash % servers`, the placement of libmemcached with
+/// `MEMCACHED_DISTRIBUTION_MODULA` and the same hash, `HashFunction::OneAtATime` being its
+/// default. Adding a server moves most of the keys.
+///
+/// The placements below are `hash % 4`, checked against a separate implementation of the hash.
+///
+/// ```rust
+/// use memento::{HashFunction, MementoCluster, Modulo};
+///
+/// let cluster = MementoCluster::builder()
+///     .servers([
+///         "10.0.1.1:11211".parse()?,
+///         "10.0.1.2:11211".parse()?,
+///         "10.0.1.3:11211".parse()?,
+///         "cache-4:11212".parse()?,
+///     ])
+///     .distribution(Modulo::new(HashFunction::OneAtATime))
+///     .build()?;
+///
+/// for (key, server) in [
+///     ("foo", "10.0.1.2:11211"),
+///     ("user:42", "10.0.1.3:11211"),
+///     ("memento", "cache-4:11212"),
+///     ("session:abc", "10.0.1.1:11211"),
+/// ] {
+///     assert_eq!(server, cluster.server(&key.parse()?).to_string());
+/// }
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Modulo {
+    hash: HashFunction,
+    weights: Vec<u32>,
+}
+
+impl Modulo {
+    pub fn new(hash: HashFunction) -> Self {
+        Self {
+            hash,
+            weights: Vec::new(),
+        }
+    }
+}
+
+impl Distribution for Modulo {
+    fn update(&mut self, servers: &[Server]) {
+        self.weights = servers.iter().map(|server| server.weight).collect();
+    }
+
+    fn server(&self, key: &Key) -> usize {
+        let total = self.weights.iter().sum::<u32>().max(1);
+        let mut bucket = self.hash.hash(key.as_bytes()) % total;
+
+        for (index, weight) in self.weights.iter().enumerate() {
+            if bucket < *weight {
+                return index;
+            }
+
+            bucket -= weight;
+        }
+
+        0
+    }
+}
+
+/// Rendezvous (highest random weight) hashing, every server scores the key and the highest score
+/// wins, so removing a server moves only its own keys. The score of a server is
+/// `weight / -ln(h)`, where `h` is the hash of `host:port-key` scaled into `(0, 1)`.
+///
+/// The score is this crate's own, other clients place the keys on different servers, so a
+/// cluster shared with them needs another distribution. The placements below only pin the
+/// scores of this implementation.
+///
+/// ```rust
+/// use memento::{HashFunction, MementoCluster, Rendezvous, Server};
+///
+/// let cluster = MementoCluster::builder()
+///     .servers([
+///         "10.0.1.1:11211".parse()?,
+///         "10.0.1.2:11211".parse()?,
+///         "10.0.1.3:11211".parse::<Server>()?.weight(2),
+///         "cache-4:11212".parse()?,
+///     ])
+///     .distribution(Rendezvous::new(HashFunction::Md5))
+///     .build()?;
+///
+/// for (key, server) in [
+///     ("foo", "cache-4:11212"),
+///     ("session:abc", "10.0.1.1:11211"),
+///     ("x", "10.0.1.2:11211"),
+/// ] {
+///     assert_eq!(server, cluster.server(&key.parse()?).to_string());
+/// }
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Rendezvous {
+    hash: HashFunction,
+    servers: Vec<(String, f64)>,
+}
+
+impl Rendezvous {
+    pub fn new(hash: HashFunction) -> Self {
+        Self {
+            hash,
+            servers: Vec::new(),
+        }
+    }
+}
+
+impl Distribution for Rendezvous {
+    fn update(&mut self, servers: &[Server]) {
+        self.servers = servers
+            .iter()
+            .map(|server| (format!("{server}-"), server.weight as f64))
+            .collect();
+    }
+
+    fn server(&self, key: &Key) -> usize {
+        let mut winner = (0, f64::MIN);
+
+        for (index, score) in self.scores(key).enumerate() {
+            if score > winner.1 {
+                winner = (index, score);
+            }
+        }
+
+        winner.0
+    }
+
+    /// Servers with the highest scores.
+    fn replicas(&self, key: &Key, count: usize) -> Vec<usize> {
+        let mut scores = self.scores(key).enumerate().collect::<Vec<_>>();
+
+        scores.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        scores
+            .into_iter()
+            .take(count)
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+impl Rendezvous {
+    fn scores<'a>(&'a self, key: &'a Key) -> impl Iterator<Item = f64> + 'a {
+        self.servers.iter().map(|(name, weight)| {
+            let hash = self.hash.hash(&[name.as_bytes(), key.as_bytes()].concat());
+
+            weight / -((hash as f64 + 0.5) / self.hash.range()).ln()
+        })
+    }
+}
+
+/// Jump consistent hashing of Lamping and Veach over the hash of the key. Only the last server
+/// may be removed without moving the keys of the others, weights are ignored.
+///
+/// ```rust
+/// use memento::{HashFunction, Jump, MementoCluster};
+///
+/// let cluster = MementoCluster::builder()
+///     .servers([
+///         "10.0.1.1:11211".parse()?,
+///         "10.0.1.2:11211".parse()?,
+///         "10.0.1.3:11211".parse()?,
+///         "cache-4:11212".parse()?,
+///     ])
+///     .distribution(Jump::new(HashFunction::Murmur3))
+///     .build()?;
+///
+/// for (key, server) in [
+///     ("bar", "cache-4:11212"),
+///     ("user:42", "10.0.1.2:11211"),
+///     ("memento", "10.0.1.1:11211"),
+/// ] {
+///     assert_eq!(server, cluster.server(&key.parse()?).to_string());
+/// }
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Jump {
+    hash: HashFunction,
+    buckets: u32,
+}
+
+impl Jump {
+    pub fn new(hash: HashFunction) -> Self {
+        Self { hash, buckets: 0 }
+    }
+
+    /// Bucket of the key among `buckets`, the same as in the paper and the other implementations.
+    ///
+    /// ```rust
+    /// use memento::Jump;
+    ///
+    /// assert_eq!(0, Jump::bucket(1, 1));
+    /// assert_eq!(43, Jump::bucket(42, 57));
+    /// assert_eq!(0, Jump::bucket(0xDEAD10CC, 1));
+    /// assert_eq!(361, Jump::bucket(0xDEAD10CC, 666));
+    /// assert_eq!(520, Jump::bucket(256, 1024));
+    /// ```
+    pub fn bucket(mut key: u64, buckets: u32) -> u32 {
+        let (mut bucket, mut next) = (-1i64, 0i64);
+
+        while next < buckets as i64 {
+            bucket = next;
+            key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+            next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+        }
+
+        bucket.max(0) as u32
+    }
+}
+
+impl Distribution for Jump {
+    fn update(&mut self, servers: &[Server]) {
+        self.buckets = servers.len() as u32;
+    }
+
+    fn server(&self, key: &Key) -> usize {
+        Self::bucket(self.hash.hash(key.as_bytes()) as u64, self.buckets) as usize
+    }
+}
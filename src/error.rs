@@ -10,6 +10,17 @@ pub enum MementoError {
     IoError(String),
     InvalidIntegerValue(String),
     InvalidStat(String),
+    InvalidResponse(String),
+    ClientError(String),
+    ServerError(String),
+    UnsupportedCommand(String),
+    AuthenticationFailed(String),
+    PoolTimeout,
+    Timeout,
+    BrokenConnection,
+    TlsError(String),
+    InvalidServer(String),
+    InvalidOpaque(String),
 }
 
 impl Display for MementoError {
@@ -21,6 +32,22 @@ impl Display for MementoError {
             Self::IoError(err) => write!(f, "{}", err),
             Self::InvalidIntegerValue(msg) => write!(f, "{}", msg),
             Self::InvalidStat(stat) => write!(f, "invalid stat {stat}"),
+            Self::InvalidResponse(resp) => write!(f, "unexpected response {resp}"),
+            Self::ClientError(msg) => write!(f, "client error: {msg}"),
+            Self::ServerError(msg) => write!(f, "server error: {msg}"),
+            Self::UnsupportedCommand(msg) => write!(f, "unsupported command: {msg}"),
+            Self::AuthenticationFailed(msg) => write!(f, "authentication failed: {msg}"),
+            Self::PoolTimeout => write!(f, "timed out waiting for a pooled connection"),
+            Self::Timeout => write!(f, "operation timed out"),
+            Self::BrokenConnection => {
+                write!(
+                    f,
+                    "connection is left in the middle of a request, reconnect"
+                )
+            }
+            Self::TlsError(msg) => write!(f, "tls error: {msg}"),
+            Self::InvalidServer(msg) => write!(f, "invalid server: {msg}"),
+            Self::InvalidOpaque(token) => write!(f, "invalid opaque token {token:?}"),
         }
     }
 }
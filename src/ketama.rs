@@ -0,0 +1,98 @@
+use crate::{Distribution, Key, Server};
+
+/// Points of a server on the continuum when all servers have the same weight.
+const POINTS_PER_SERVER: f32 = 160.0;
+
+/// Points taken from a single md5 digest.
+const POINTS_PER_HASH: u32 = 4;
+
+/// Weighted ketama continuum compatible with libmemcached, so keys are placed on the same
+/// servers as by the clients built on it, `php-memcached` with `OPT_LIBKETAMA_COMPATIBLE` included.
+///
+/// Every server gets `160 * weight / total weight * servers` points rounded down to a multiple
+/// of four. The points of a server are md5 digests of `host-i`, or `host:port-i` when the port
+/// is not 11211, each digest is split into four little-endian numbers. A key belongs
+/// to the first point that is not less than the md5 of the key, wrapping around the continuum.
+/// Adding or removing a server moves only a share of the keys proportional to its weight.
+///
+/// This is the distribution of `MementoCluster` unless another one is set.
+#[derive(Debug, Clone, Default)]
+pub struct Ketama {
+    points: Vec<(u32, usize)>,
+}
+
+impl Distribution for Ketama {
+    fn update(&mut self, servers: &[Server]) {
+        let total = servers.iter().map(|server| server.weight).sum::<u32>() as f32;
+        let live = servers.len() as f32;
+
+        let mut points = Vec::new();
+
+        for (index, server) in servers.iter().enumerate() {
+            // the float arithmetic follows libmemcached to get the same number of points.
+            let share = server.weight as f32 / total;
+            let hashes =
+                ((share * POINTS_PER_SERVER / 4.0 * live) as f64 + 0.0000000001).floor() as u32;
+
+            for point in 0..hashes {
+                let name = match server.port {
+                    11211 => format!("{}-{point}", server.host),
+                    port => format!("{}:{port}-{point}", server.host),
+                };
+
+                let digest = md5::compute(name);
+
+                for alignment in 0..POINTS_PER_HASH as usize {
+                    points.push((le(&digest[alignment * 4..]), index));
+                }
+            }
+        }
+
+        points.sort_by_key(|(point, _)| *point);
+
+        self.points = points;
+    }
+
+    fn server(&self, key: &Key) -> usize {
+        self.points
+            .get(self.position(key))
+            .or_else(|| self.points.first())
+            .map_or(0, |(_, index)| *index)
+    }
+
+    /// Distinct servers met walking the continuum from the point of the key.
+    fn replicas(&self, key: &Key, count: usize) -> Vec<usize> {
+        let mut servers = Vec::with_capacity(count);
+
+        for (_, index) in self
+            .points
+            .iter()
+            .cycle()
+            .skip(self.position(key))
+            .take(self.points.len())
+        {
+            if servers.len() == count {
+                break;
+            }
+
+            if !servers.contains(index) {
+                servers.push(*index);
+            }
+        }
+
+        servers
+    }
+}
+
+impl Ketama {
+    /// Position of the first point not less than the md5 of the key.
+    fn position(&self, key: &Key) -> usize {
+        let hash = le(&md5::compute(key.as_bytes())[..]);
+
+        self.points.partition_point(|(point, _)| *point < hash)
+    }
+}
+
+fn le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
@@ -1,25 +1,68 @@
 extern crate core;
 
+mod address;
+mod backoff;
+mod binary;
+mod builder;
+mod cluster;
+mod codec;
 mod command;
 mod connection;
+mod distribution;
 mod error;
+mod ketama;
 mod memento;
+mod meta;
+mod pipeline;
+mod pool;
+mod sasl;
+mod shared;
+mod timeout;
+#[cfg(feature = "tls")]
+mod tls;
 
-pub use self::{command::*, error::*, memento::*};
-use std::fmt::Debug;
-
-use tokio::net::ToSocketAddrs;
+#[cfg(feature = "tls")]
+pub use self::tls::TlsConfig;
+pub use self::{
+    address::*, backoff::*, binary::*, builder::*, cluster::*, codec::*, command::*,
+    distribution::*, error::*, ketama::*, memento::*, meta::*, pipeline::*, pool::*, sasl::*,
+    shared::*, timeout::*,
+};
+use bytes::Bytes;
 
 pub type Result<T> = std::result::Result<T, MementoError>;
 
+/// Single unit of a server response: either a text line without the trailing `\r\n`
+/// or a data block that follows a `VALUE` or `VA` line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    Line(String),
+    Data(Bytes),
+}
+
+impl Frame {
+    /// Text of the frame if it is a line.
+    pub fn line(&self) -> Option<&str> {
+        match self {
+            Self::Line(line) => Some(line),
+            Self::Data(..) => None,
+        }
+    }
+
+    /// Whether the frame is the `END` line that terminates retrieval and stats responses.
+    pub fn is_end(&self) -> bool {
+        self.line() == Some("END")
+    }
+}
+
 pub trait ToCommandResponse: Default {
-    fn create<T>(frames: Vec<T>, cmd: Command) -> Result<Option<Self>>
-    where
-        T: ToString + Debug + Default;
+    /// Builds the response from the complete list of frames sent by the server,
+    /// `None` means the frames are not a valid reply to the command.
+    fn create(frames: Vec<Frame>, cmd: Command) -> Result<Option<Self>>;
 }
 
 ///
-/// ```rust
+/// ```rust,no_run
 /// use memento::Result;
 ///
 /// #[tokio::main]
@@ -29,6 +72,6 @@ pub trait ToCommandResponse: Default {
 ///     Ok(())
 /// }
 ///```
-pub async fn new<A: ToSocketAddrs>(addr: A) -> Result<Memento> {
+pub async fn new<A: Address>(addr: A) -> Result<Memento> {
     Memento::connect(addr).await
 }
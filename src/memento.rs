@@ -1,9 +1,17 @@
 use crate::connection::Connection;
 use crate::{
-    Add, Append, Command, CommandResp, Decr, Incr, Item, Key, Prepend, Replace, Set,
-    ToCommandResponse,
+    Add, Address, Append, Cas, Command, CommandResp, Decr, Delete, FlushAll, Gat, Incr, Item, Key,
+    MementoBuilder, MementoError, MetaArithmetic, MetaDebug, MetaDelete, MetaGet, MetaResponse,
+    MetaSet, Pipeline, Prepend, Replace, Set, Timeouts, ToCommandResponse, Touch,
 };
-use tokio::net::{TcpStream, ToSocketAddrs};
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Conservative limit of a single command line, including the command name and `\r\n`.
+const MAX_LINE_LENGTH: usize = 2048;
 
 #[derive(Debug)]
 pub struct Memento {
@@ -13,25 +21,148 @@ pub struct Memento {
 unsafe impl Send for Memento {}
 
 impl Memento {
+    ///
+    /// ```rust,no_run
+    /// use memento::Protocol;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let memento = memento::Memento::builder()
+    ///         .protocol(Protocol::Binary)
+    ///         .connect("localhost:11211")
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn builder() -> MementoBuilder {
+        MementoBuilder::default()
+    }
+
+    pub(crate) fn from_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    pub(crate) fn is_broken(&self) -> bool {
+        self.connection.is_broken()
+    }
+
+    /// Overrides the timeouts of the next call, the timeouts that are not set are taken
+    /// from the builder. A timed out call leaves the connection unusable, so the next call
+    /// reconnects first.
+    ///
+    /// ```rust,no_run
+    /// use memento::{MementoError, Timeouts};
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let response = memento
+    ///         .with_timeouts(Timeouts::default().read(Duration::from_millis(10)))
+    ///         .get("x".parse()?)
+    ///         .await;
+    ///
+    ///     if let Err(MementoError::Timeout) = response {
+    ///         memento.reconnect().await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// Without `MementoBuilder::reconnect` the connection stays unusable until it is reopened.
     ///
     /// ```rust
-    /// use tokio::net::TcpStream;
+    /// use memento::{CommandResp, MementoBuilder, MementoError, Timeouts};
+    /// use std::time::Duration;
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
     ///
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
-    ///     let memento = memento::Memento::from_stream(TcpStream::connect("localhost:11211").await?);
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let addr = listener.local_addr()?;
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok((stream, _)) = listener.accept().await {
+    ///             tokio::spawn(async move {
+    ///                 let mut stream = BufReader::new(stream);
+    ///                 let mut line = String::new();
+    ///
+    ///                 // `get` stalls, `version` is answered.
+    ///                 while stream.read_line(&mut line).await? > 0 {
+    ///                     if line == "version\r\n" {
+    ///                         stream.write_all(b"VERSION 1.6.21\r\n").await?;
+    ///                     }
+    ///
+    ///                     line.clear();
+    ///                 }
+    ///
+    ///                 Ok::<_, std::io::Error>(())
+    ///             });
+    ///         }
+    ///     });
+    ///
+    ///     let mut memento = MementoBuilder::default().reconnect(None).connect(addr).await?;
+    ///
+    ///     let response = memento
+    ///         .with_timeouts(Timeouts::default().read(Duration::from_millis(50)))
+    ///         .get("x".parse()?)
+    ///         .await;
+    ///
+    ///     assert!(matches!(response, Err(MementoError::Timeout)));
+    ///     assert!(matches!(memento.version().await, Err(MementoError::BrokenConnection)));
+    ///
+    ///     memento.reconnect().await?;
+    ///
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(..)));
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn from_stream(stream: TcpStream) -> Self {
-        Self {
-            connection: Connection::from_stream(stream),
-        }
+    pub fn with_timeouts(&mut self, timeouts: Timeouts) -> &mut Self {
+        self.connection.set_next_timeouts(timeouts);
+        self
     }
 
+    /// Works over any byte stream: a `TcpStream`, a `UnixStream`, an in-memory `DuplexStream`
+    /// or a custom wrapper.
     ///
     /// ```rust
+    /// use memento::{CommandResp, Memento};
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let (client, mut server) = tokio::io::duplex(1024);
+    ///
+    ///     tokio::spawn(async move {
+    ///         let mut request = [0; 9];
+    ///
+    ///         server.read_exact(&mut request).await?;
+    ///         assert_eq!(b"version\r\n", &request);
+    ///
+    ///         server.write_all(b"VERSION 1.6.21\r\n").await
+    ///     });
+    ///
+    ///     let mut memento = Memento::from_stream(client);
+    ///
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(version) if version == "1.6.21"));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        MementoBuilder::default().from_stream(stream)
+    }
+
+    ///
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let memento = memento::Memento::connect("localhost:11211").await?;
@@ -39,14 +170,26 @@ impl Memento {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn connect<A: ToSocketAddrs>(addr: A) -> crate::Result<Self> {
-        Ok(Self {
-            connection: Connection::connect(addr).await?,
-        })
+    pub async fn connect<A: Address>(addr: A) -> crate::Result<Self> {
+        MementoBuilder::default().connect(addr).await
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let memento = memento::Memento::connect_unix("/var/run/memcached/memcached.sock").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(unix)]
+    pub async fn connect_unix<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
+        MementoBuilder::default().connect_unix(path).await
+    }
+
+    ///
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -61,7 +204,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -76,7 +219,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -91,7 +234,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -106,7 +249,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -130,7 +273,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -153,8 +296,40 @@ impl Memento {
         self.call(Command::Gets(keys)).await
     }
 
+    /// Fetches all keys with `get k1 k2 ...` and returns the hits, missing keys are
+    /// simply absent from the map. Keys are split into as many commands as needed
+    /// to keep every command line within the server limit.
     ///
-    /// ```rust
+    /// ```rust,no_run
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let keys = vec!["header".parse()?, "footer".parse()?];
+    ///
+    ///     for (key, item) in memento.get_multi(&keys).await? {
+    ///         println!("{key}: {item}");
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_multi(&mut self, keys: &[Key]) -> crate::Result<HashMap<Key, Item>> {
+        let mut items = HashMap::with_capacity(keys.len());
+
+        for chunk in chunks(keys, self.connection.key_prefix(), "get") {
+            match self.call(Command::GetMulti(chunk)).await? {
+                CommandResp::Values(values) => items.extend(values),
+                CommandResp::NotFound => {}
+                resp => return Err(MementoError::InvalidResponse(format!("{:?}", resp))),
+            }
+        }
+
+        Ok(items)
+    }
+
+    ///
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -169,7 +344,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -184,7 +359,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -195,11 +370,155 @@ impl Memento {
     /// }
     /// ```
     pub async fn delete(&mut self, key: Key) -> crate::Result<CommandResp> {
-        self.call(Command::Delete(key)).await
+        self.call(Command::Delete(Delete::new(key))).await
     }
 
+    /// Updates the expiration time of the key without fetching it,
+    /// responds with `Touched` or `NotFound`.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     memento.touch("x".parse()?, Duration::from_secs(60)).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn touch(&mut self, key: Key, ttl: Duration) -> crate::Result<CommandResp> {
+        self.call(Command::Touch(Touch::new(key, ttl))).await
+    }
+
+    /// Fetches the keys and updates their expiration time, in one command unless there are
+    /// too many keys for a single line.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let response = memento.gat(Duration::from_secs(60), vec!["session".parse()?]).await?;
+    ///
+    ///     if let memento::CommandResp::Values(values) = response {
+    ///         for (key, item) in values {
+    ///             println!("{key}: {item}")
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// Long lists of keys are split over several commands, as by `get_multi`.
     ///
     /// ```rust
+    /// use memento::{CommandResp, Key, Memento};
+    /// use std::time::Duration;
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let (client, server) = tokio::io::duplex(64 * 1024);
+    ///
+    ///     // replies with the first key of every line only.
+    ///     tokio::spawn(async move {
+    ///         let mut server = BufReader::new(server);
+    ///         let mut line = String::new();
+    ///
+    ///         while server.read_line(&mut line).await? > 0 {
+    ///             assert!(line.len() <= 2048);
+    ///
+    ///             let key = line.split_whitespace().nth(2).unwrap_or_default();
+    ///
+    ///             server.write_all(format!("VALUE {key} 0 1\r\nx\r\nEND\r\n").as_bytes()).await?;
+    ///             line.clear();
+    ///         }
+    ///
+    ///         Ok::<_, std::io::Error>(())
+    ///     });
+    ///
+    ///     let keys = (0..500)
+    ///         .map(|i| format!("session:{i:08}").parse())
+    ///         .collect::<memento::Result<Vec<Key>>>()?;
+    ///
+    ///     let mut memento = Memento::from_stream(client);
+    ///
+    ///     match memento.gat(Duration::from_secs(60), keys).await? {
+    ///         CommandResp::Values(values) => assert_eq!(5, values.len()),
+    ///         resp => panic!("{:?}", resp),
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn gat(&mut self, ttl: Duration, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        let cmd = format!("gat {}", ttl.as_secs());
+
+        self.retrieve_chunks(&keys, &cmd, |keys| Command::Gat(Gat::new(ttl, keys)))
+            .await
+    }
+
+    /// Same as `gat`, but the returned items also carry their cas unique.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let response = memento.gats(Duration::from_secs(60), vec!["session".parse()?]).await?;
+    ///
+    ///     if let memento::CommandResp::Values(values) = response {
+    ///         for (key, item) in values {
+    ///             println!("{key}: {item} ({:?})", item.cas())
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn gats(&mut self, ttl: Duration, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        let cmd = format!("gats {}", ttl.as_secs());
+
+        self.retrieve_chunks(&keys, &cmd, |keys| Command::Gats(Gat::new(ttl, keys)))
+            .await
+    }
+
+    /// Sends a retrieval command per chunk of the keys, responds with the `Values` of all
+    /// of them or `NotFound` as a single command does.
+    async fn retrieve_chunks<F>(
+        &mut self,
+        keys: &[Key],
+        cmd: &str,
+        f: F,
+    ) -> crate::Result<CommandResp>
+    where
+        F: Fn(Vec<Key>) -> Command,
+    {
+        let mut values = Vec::with_capacity(keys.len());
+
+        for chunk in chunks(keys, self.connection.key_prefix(), cmd) {
+            match self.call(f(chunk)).await? {
+                CommandResp::Values(chunk) => values.extend(chunk),
+                CommandResp::NotFound => {}
+                resp => return Err(MementoError::InvalidResponse(format!("{:?}", resp))),
+            }
+        }
+
+        match values.is_empty() {
+            true => Ok(CommandResp::NotFound),
+            false => Ok(CommandResp::Values(values)),
+        }
+    }
+
+    ///
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -213,8 +532,258 @@ impl Memento {
         self.call(Command::Replace(Replace::new(key, item))).await
     }
 
+    /// Stores the item only if nobody else has updated the key since the cas unique was fetched
+    /// with `gets`. Responds with `Stored` on success, `Exists` on a conflicting update
+    /// and `NotFound` when the key was deleted or expired.
+    ///
+    /// ```rust,no_run
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     if let memento::CommandResp::Values(values) = memento.gets(vec!["x".parse()?]).await? {
+    ///         for (key, item) in values {
+    ///             let cas = item.cas().unwrap_or_default();
+    ///
+    ///             memento.cas(key, memento::Item::timeless("z"), cas).await?;
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn cas(&mut self, key: Key, item: Item, cas: u64) -> crate::Result<CommandResp> {
+        self.call(Command::Cas(Cas::new(key, item, cas))).await
+    }
+
+    /// Read-modify-write of the key: fetches the item with `gets`, computes the new one with
+    /// the closure and stores it with `cas`, starting over up to `retries` times when another
+    /// client won the race. Responds with `Stored` on success, `NotFound` when there is
+    /// nothing to update and `Exists` when all attempts conflicted.
+    ///
+    /// ```rust,no_run
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     memento
+    ///         .update("visits".parse()?, 3, |item| {
+    ///             let visits = item.to_string().parse::<u64>().unwrap_or_default();
+    ///
+    ///             memento::Item::timeless(visits + 1)
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn update<F>(
+        &mut self,
+        key: Key,
+        retries: usize,
+        mut f: F,
+    ) -> crate::Result<CommandResp>
+    where
+        F: FnMut(&Item) -> Item,
+    {
+        let mut response = CommandResp::NotFound;
+
+        for _ in 0..=retries {
+            let item = match self.gets(vec![key.clone()]).await? {
+                CommandResp::Values(mut values) if !values.is_empty() => values.remove(0).1,
+                resp => return Ok(resp),
+            };
+
+            let cas = item
+                .cas()
+                .ok_or_else(|| MementoError::InvalidItem(item.to_string()))?;
+
+            response = self.cas(key.clone(), f(&item), cas).await?;
+
+            match response {
+                CommandResp::Exists => continue,
+                resp => return Ok(resp),
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Invalidates all items on the server, immediately or after the delay.
+    /// Responds with `Ok`.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     memento.flush_all(Some(Duration::from_secs(10))).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn flush_all(&mut self, delay: Option<Duration>) -> crate::Result<CommandResp> {
+        self.call(Command::FlushAll(FlushAll::new(delay))).await
+    }
+
+    /// Meta get, see `MetaGet` for the available flags.
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use memento::{MetaGet, MetaStatus};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let response = memento
+    ///         .meta_get(
+    ///             MetaGet::new("x".parse()?)
+    ///                 .return_value()
+    ///                 .return_ttl()
+    ///                 .vivify(Duration::from_secs(30)),
+    ///         )
+    ///         .await?;
+    ///
+    ///     if response.win() {
+    ///         println!("miss, recache the value");
+    ///     } else if response.status() == MetaStatus::Value {
+    ///         println!("{:?} expires in {:?}", response.value(), response.ttl());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn meta_get(&mut self, cmd: MetaGet) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaGet(cmd)).await
+    }
+
+    /// Meta set, see `MetaSet` for the available flags.
+    ///
+    /// ```rust,no_run
+    /// use memento::{Item, MetaSet};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let response = memento
+    ///         .meta_set(MetaSet::new("x".parse()?, Item::timeless("y")).return_cas())
+    ///         .await?;
+    ///
+    ///     println!("{:?} {:?}", response.status(), response.cas());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn meta_set(&mut self, cmd: MetaSet) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaSet(cmd)).await
+    }
+
+    /// Meta delete, see `MetaDelete` for the available flags.
+    ///
+    /// ```rust,no_run
+    /// use memento::MetaDelete;
     ///
-    /// ```rust
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     memento.meta_delete(MetaDelete::new("x".parse()?).invalidate()).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn meta_delete(&mut self, cmd: MetaDelete) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaDelete(cmd)).await
+    }
+
+    /// Meta arithmetic, see `MetaArithmetic` for the available flags.
+    ///
+    /// ```rust,no_run
+    /// use memento::MetaArithmetic;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let response = memento
+    ///         .meta_arithmetic(MetaArithmetic::new("x".parse()?).delta(5).return_value())
+    ///         .await?;
+    ///
+    ///     println!("{:?}", response.value());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn meta_arithmetic(&mut self, cmd: MetaArithmetic) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaArithmetic(cmd)).await
+    }
+
+    /// Meta no-op, the server responds with `MN` after all the preceding replies.
+    ///
+    /// ```rust,no_run
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     memento.meta_noop().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn meta_noop(&mut self) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaNoop).await
+    }
+
+    /// Meta debug, returns internal details of the item.
+    ///
+    /// ```rust,no_run
+    /// use memento::MetaDebug;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     for (name, value) in memento.meta_debug(MetaDebug::new("x".parse()?)).await?.debug() {
+    ///         println!("{name}: {value}");
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn meta_debug(&mut self, cmd: MetaDebug) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaDebug(cmd)).await
+    }
+
+    async fn meta(&mut self, cmd: Command) -> crate::Result<MetaResponse> {
+        match self.call(cmd).await? {
+            CommandResp::Meta(response) => Ok(response),
+            resp => Err(MementoError::InvalidResponse(format!("{:?}", resp))),
+        }
+    }
+
+    /// Opens a new connection to the same server, authenticating it again when
+    /// the original one was created with credentials.
+    ///
+    /// ```rust,no_run
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     memento.reconnect().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn reconnect(&mut self) -> crate::Result<()> {
+        self.connection.reconnect().await
+    }
+
+    ///
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -229,7 +798,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -244,7 +813,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// #[tokio::main]
     /// async fn main() -> memento::Result<()> {
     ///     let mut memento = memento::new("localhost:11211").await?;
@@ -259,7 +828,7 @@ impl Memento {
     }
 
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// use tokio::net::TcpStream;
     /// use memento::{Command, Incr, CommandResp};
     ///
@@ -278,4 +847,155 @@ impl Memento {
     pub async fn call<T: ToCommandResponse>(&mut self, cmd: Command) -> crate::Result<T> {
         self.connection.execute(cmd).await
     }
+
+    /// Writes all commands at once and reads a response per command, see `Pipeline`.
+    ///
+    /// ```rust,no_run
+    /// use memento::{Command, CommandResp, Delete, Incr};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let responses = memento
+    ///         .call_many::<CommandResp>(vec![
+    ///             Command::Incr(Incr::new("x".parse()?, 1)),
+    ///             Command::Delete(Delete::new("y".parse()?)),
+    ///         ])
+    ///         .await;
+    ///
+    ///     println!("{:#?}", responses);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// A command that cannot be encoded fails alone, the rest are still sent.
+    ///
+    /// ```rust
+    /// use memento::{Command, CommandResp, Incr, Memento, MementoError};
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let (client, mut server) = tokio::io::duplex(1024);
+    ///
+    ///     tokio::spawn(async move {
+    ///         let mut request = [0; 9];
+    ///
+    ///         for _ in 0..2 {
+    ///             server.read_exact(&mut request).await?;
+    ///             server.write_all(b"VERSION 1.6.21\r\n").await?;
+    ///         }
+    ///
+    ///         Ok::<_, std::io::Error>(())
+    ///     });
+    ///
+    ///     let mut memento = Memento::from_stream(client);
+    ///
+    ///     // the text protocol has no initial value for `incr`.
+    ///     let responses = memento
+    ///         .call_many::<CommandResp>(vec![
+    ///             Command::Incr(Incr::new("x".parse()?, 1).initial(5)),
+    ///             Command::Version,
+    ///         ])
+    ///         .await;
+    ///
+    ///     assert!(matches!(responses[0], Err(MementoError::UnsupportedCommand(..))));
+    ///     assert!(matches!(responses[1], Ok(CommandResp::Version(..))));
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(..)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// A connection closed during the batch, as by a `quit`, is not used again.
+    ///
+    /// ```rust
+    /// use memento::{Command, CommandResp, Memento, MementoError};
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let (client, server) = tokio::io::duplex(1024);
+    ///
+    ///     tokio::spawn(async move {
+    ///         let mut server = BufReader::new(server);
+    ///         let mut line = String::new();
+    ///
+    ///         server.read_line(&mut line).await?;
+    ///         server.write_all(b"VERSION 1.6.21\r\n").await?;
+    ///         server.read_line(&mut line).await?;
+    ///
+    ///         Ok::<_, std::io::Error>(())
+    ///     });
+    ///
+    ///     let mut memento = Memento::from_stream(client);
+    ///
+    ///     let responses = memento
+    ///         .call_many::<CommandResp>(vec![Command::Version, Command::Quit])
+    ///         .await;
+    ///
+    ///     assert!(matches!(responses[0], Ok(CommandResp::Version(..))));
+    ///     assert!(matches!(responses[1], Ok(CommandResp::NoResponse)));
+    ///     assert!(matches!(memento.version().await, Err(MementoError::BrokenConnection)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn call_many<T: ToCommandResponse>(
+        &mut self,
+        cmds: Vec<Command>,
+    ) -> Vec<crate::Result<T>> {
+        self.connection.execute_many(cmds).await
+    }
+
+    /// Starts a batch of commands sent in one round trip.
+    ///
+    /// ```rust,no_run
+    /// use memento::Item;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let mut memento = memento::new("localhost:11211").await?;
+    ///
+    ///     let responses = memento
+    ///         .pipeline()
+    ///         .set("x".parse()?, Item::timeless("y"))
+    ///         .get("x".parse()?)
+    ///         .execute()
+    ///         .await;
+    ///
+    ///     println!("{:#?}", responses);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn pipeline(&mut self) -> Pipeline<'_> {
+        Pipeline::new(self)
+    }
+}
+
+/// Groups keys so that `<cmd> <keys>\r\n` of every group fits into `MAX_LINE_LENGTH`
+/// once every key gets the prefix of the connection.
+pub(crate) fn chunks(keys: &[Key], prefix: &str, cmd: &str) -> Vec<Vec<Key>> {
+    let mut chunks: Vec<Vec<Key>> = Vec::new();
+    let mut len = MAX_LINE_LENGTH;
+
+    for key in keys {
+        let key_len = prefix.len() + key.to_string().len() + 1;
+
+        if len + key_len > MAX_LINE_LENGTH - cmd.len() - "\r\n".len() {
+            chunks.push(Vec::new());
+            len = 0;
+        }
+
+        len += key_len;
+
+        if let Some(chunk) = chunks.last_mut() {
+            chunk.push(key.clone());
+        }
+    }
+
+    chunks
 }
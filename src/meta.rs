@@ -0,0 +1,844 @@
+use crate::{Frame, Item, Key, MementoError};
+use bytes::{BufMut, Bytes, BytesMut};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Flags shared by all meta commands: the command line is `<name> <key> <flags>*`.
+#[derive(Debug, Clone)]
+struct MetaFlags {
+    key: Key,
+    flags: Vec<String>,
+    quiet: bool,
+}
+
+impl MetaFlags {
+    fn new(key: Key) -> Self {
+        Self {
+            key,
+            flags: Vec::new(),
+            quiet: false,
+        }
+    }
+
+    fn push<T: ToString>(&mut self, flag: T) {
+        self.flags.push(flag.to_string());
+    }
+
+    /// Adds the opaque token unless the command already has one.
+    fn tag(&mut self, token: String) -> Option<String> {
+        if self.flags.iter().any(|flag| flag.starts_with('O')) {
+            return None;
+        }
+
+        self.push(format!("O{token}"));
+
+        Some(token)
+    }
+
+    /// Puts the prefix in front of the key, base64 encoded keys cannot be prefixed.
+    fn prefix(&mut self, prefix: &str) -> crate::Result<()> {
+        if self.flags.iter().any(|flag| flag == "b") {
+            return Err(MementoError::UnsupportedCommand(
+                "key prefix cannot be applied to a base64 encoded key".to_string(),
+            ));
+        }
+
+        self.key = self.key.prefixed(prefix)?;
+
+        Ok(())
+    }
+
+    fn write(&self, dst: &mut BytesMut, name: &str, len: Option<usize>) {
+        dst.put(format!("{name} {key}", key = self.key).as_bytes());
+
+        if let Some(len) = len {
+            dst.put(format!(" {len}").as_bytes());
+        }
+
+        for flag in &self.flags {
+            dst.put(format!(" {flag}").as_bytes());
+        }
+
+        if self.quiet {
+            dst.put(&b" q"[..]);
+        }
+
+        dst.put(&b"\r\n"[..]);
+    }
+}
+
+/// Opaque token of a meta command, up to 32 bytes without spaces or control characters.
+///
+/// ```rust
+/// use memento::{MementoError, Opaque};
+///
+/// let opaque = "42".parse::<Opaque>()?;
+///
+/// assert!(matches!("x T0".parse::<Opaque>(), Err(MementoError::InvalidOpaque(..))));
+/// assert!(matches!("x".repeat(33).parse::<Opaque>(), Err(MementoError::InvalidOpaque(..))));
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Opaque {
+    value: String,
+}
+
+impl FromStr for Opaque {
+    type Err = MementoError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.is_empty()
+            || value.len() > 32
+            || value.bytes().any(|byte| byte <= b' ' || byte == 0x7f)
+        {
+            return Err(MementoError::InvalidOpaque(value.to_string()));
+        }
+
+        Ok(Opaque {
+            value: value.to_string(),
+        })
+    }
+}
+
+impl Display for Opaque {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+/// Storage mode of the `ms` command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetaMode {
+    Add,
+    Append,
+    Prepend,
+    Replace,
+    Set,
+}
+
+/// The `mg` command, responds with `VA` when the value is requested, `HD` on a hit otherwise
+/// and `EN` on a miss.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use memento::MetaGet;
+///
+/// let cmd = MetaGet::new("x".parse()?)
+///     .return_value()
+///     .return_ttl()
+///     .return_cas()
+///     .recache(Duration::from_secs(30))
+///     .opaque("42".parse()?);
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetaGet {
+    meta: MetaFlags,
+}
+
+impl MetaGet {
+    pub fn new(key: Key) -> Self {
+        Self {
+            meta: MetaFlags::new(key),
+        }
+    }
+
+    /// Key is base64 encoded, `b` flag.
+    pub fn base64(mut self) -> Self {
+        self.meta.push("b");
+        self
+    }
+
+    /// Return the item value, `v` flag.
+    pub fn return_value(mut self) -> Self {
+        self.meta.push("v");
+        self
+    }
+
+    /// Return the item cas unique, `c` flag.
+    pub fn return_cas(mut self) -> Self {
+        self.meta.push("c");
+        self
+    }
+
+    /// Return the item client flags, `f` flag.
+    pub fn return_flags(mut self) -> Self {
+        self.meta.push("f");
+        self
+    }
+
+    /// Return whether the item has been hit before, `h` flag.
+    pub fn return_hit(mut self) -> Self {
+        self.meta.push("h");
+        self
+    }
+
+    /// Return the key, `k` flag.
+    pub fn return_key(mut self) -> Self {
+        self.meta.push("k");
+        self
+    }
+
+    /// Return the time since the item was last accessed in seconds, `l` flag.
+    pub fn return_last_access(mut self) -> Self {
+        self.meta.push("l");
+        self
+    }
+
+    /// Return the item size, `s` flag.
+    pub fn return_size(mut self) -> Self {
+        self.meta.push("s");
+        self
+    }
+
+    /// Return the remaining TTL in seconds, `-1` for unlimited, `t` flag.
+    pub fn return_ttl(mut self) -> Self {
+        self.meta.push("t");
+        self
+    }
+
+    /// Do not bump the item in the LRU, `u` flag.
+    pub fn no_bump(mut self) -> Self {
+        self.meta.push("u");
+        self
+    }
+
+    /// Opaque token echoed back in the response, `O` flag.
+    pub fn opaque(mut self, opaque: Opaque) -> Self {
+        self.meta.push(format!("O{opaque}"));
+        self
+    }
+
+    /// Use the token as the new cas unique if the item is modified, `E` flag.
+    pub fn new_cas(mut self, cas: u64) -> Self {
+        self.meta.push(format!("E{cas}"));
+        self
+    }
+
+    /// Create an empty item with the TTL on a miss and win the right to recache it, `N` flag.
+    pub fn vivify(mut self, ttl: Duration) -> Self {
+        self.meta.push(format!("N{}", ttl.as_secs()));
+        self
+    }
+
+    /// Win the right to recache the item when its remaining TTL is below the token, `R` flag.
+    pub fn recache(mut self, ttl: Duration) -> Self {
+        self.meta.push(format!("R{}", ttl.as_secs()));
+        self
+    }
+
+    /// Update the TTL of the item, `T` flag.
+    pub fn touch(mut self, ttl: Duration) -> Self {
+        self.meta.push(format!("T{}", ttl.as_secs()));
+        self
+    }
+
+    /// Suppress the `EN` reply on a miss, `q` flag.
+    pub fn quiet(mut self) -> Self {
+        self.meta.quiet = true;
+        self
+    }
+
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.meta.quiet
+    }
+
+    pub(crate) fn tag(&mut self, token: String) -> Option<String> {
+        self.meta.tag(token)
+    }
+
+    pub(crate) fn prefix(&mut self, prefix: &str) -> crate::Result<()> {
+        self.meta.prefix(prefix)
+    }
+
+    pub(crate) fn key(&self) -> &Key {
+        &self.meta.key
+    }
+
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        self.meta.write(dst, "mg", None);
+    }
+}
+
+/// The `ms` command, responds with `HD` when stored, `NS` when not stored,
+/// `EX` on a cas mismatch and `NF` when the item to compare with is missing.
+/// Client flags and expiration time are taken from the item.
+///
+/// ```rust
+/// use memento::{Item, MetaMode, MetaSet};
+///
+/// let cmd = MetaSet::new("x".parse()?, Item::timeless("y").with_flags(1))
+///     .mode(MetaMode::Add)
+///     .return_cas();
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetaSet {
+    meta: MetaFlags,
+    value: Bytes,
+}
+
+impl MetaSet {
+    pub fn new(key: Key, item: Item) -> Self {
+        let mut meta = MetaFlags::new(key);
+
+        if item.flags() != 0 {
+            meta.push(format!("F{}", item.flags()));
+        }
+
+        if let Some(ttl) = item.ttl() {
+            meta.push(format!("T{}", ttl.as_secs()));
+        }
+
+        Self {
+            meta,
+            value: item.into_bytes(),
+        }
+    }
+
+    /// Key is base64 encoded, `b` flag.
+    pub fn base64(mut self) -> Self {
+        self.meta.push("b");
+        self
+    }
+
+    /// Return the cas unique of the stored item, `c` flag.
+    pub fn return_cas(mut self) -> Self {
+        self.meta.push("c");
+        self
+    }
+
+    /// Return the key, `k` flag.
+    pub fn return_key(mut self) -> Self {
+        self.meta.push("k");
+        self
+    }
+
+    /// Store only if the cas unique of the item matches, `C` flag.
+    pub fn compare_cas(mut self, cas: u64) -> Self {
+        self.meta.push(format!("C{cas}"));
+        self
+    }
+
+    /// Use the token as the new cas unique, `E` flag.
+    pub fn new_cas(mut self, cas: u64) -> Self {
+        self.meta.push(format!("E{cas}"));
+        self
+    }
+
+    /// With `compare_cas`, store the item as stale if the cas unique is older, `I` flag.
+    pub fn invalidate(mut self) -> Self {
+        self.meta.push("I");
+        self
+    }
+
+    /// Storage mode, `M` flag. Default is `MetaMode::Set`.
+    pub fn mode(mut self, mode: MetaMode) -> Self {
+        self.meta.push(match mode {
+            MetaMode::Add => "ME",
+            MetaMode::Append => "MA",
+            MetaMode::Prepend => "MP",
+            MetaMode::Replace => "MR",
+            MetaMode::Set => "MS",
+        });
+        self
+    }
+
+    /// Opaque token echoed back in the response, `O` flag.
+    pub fn opaque(mut self, opaque: Opaque) -> Self {
+        self.meta.push(format!("O{opaque}"));
+        self
+    }
+
+    /// Suppress the `HD` reply on success, `q` flag.
+    pub fn quiet(mut self) -> Self {
+        self.meta.quiet = true;
+        self
+    }
+
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.meta.quiet
+    }
+
+    pub(crate) fn tag(&mut self, token: String) -> Option<String> {
+        self.meta.tag(token)
+    }
+
+    pub(crate) fn prefix(&mut self, prefix: &str) -> crate::Result<()> {
+        self.meta.prefix(prefix)
+    }
+
+    pub(crate) fn key(&self) -> &Key {
+        &self.meta.key
+    }
+
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        self.meta.write(dst, "ms", Some(self.value.len()));
+        dst.put(self.value.clone());
+        dst.put(&b"\r\n"[..]);
+    }
+}
+
+/// The `md` command, responds with `HD` when deleted, `NF` when missing
+/// and `EX` on a cas mismatch.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use memento::MetaDelete;
+///
+/// let cmd = MetaDelete::new("x".parse()?)
+///     .invalidate()
+///     .touch(Duration::from_secs(30));
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetaDelete {
+    meta: MetaFlags,
+}
+
+impl MetaDelete {
+    pub fn new(key: Key) -> Self {
+        Self {
+            meta: MetaFlags::new(key),
+        }
+    }
+
+    /// Key is base64 encoded, `b` flag.
+    pub fn base64(mut self) -> Self {
+        self.meta.push("b");
+        self
+    }
+
+    /// Return the key, `k` flag.
+    pub fn return_key(mut self) -> Self {
+        self.meta.push("k");
+        self
+    }
+
+    /// Delete only if the cas unique of the item matches, `C` flag.
+    pub fn compare_cas(mut self, cas: u64) -> Self {
+        self.meta.push(format!("C{cas}"));
+        self
+    }
+
+    /// Use the token as the new cas unique when the item is invalidated, `E` flag.
+    pub fn new_cas(mut self, cas: u64) -> Self {
+        self.meta.push(format!("E{cas}"));
+        self
+    }
+
+    /// Mark the item as stale instead of deleting it, `I` flag.
+    pub fn invalidate(mut self) -> Self {
+        self.meta.push("I");
+        self
+    }
+
+    /// Update the TTL of the invalidated item, `T` flag.
+    pub fn touch(mut self, ttl: Duration) -> Self {
+        self.meta.push(format!("T{}", ttl.as_secs()));
+        self
+    }
+
+    /// Remove the value but keep the item with its client flags, `x` flag.
+    pub fn remove_value(mut self) -> Self {
+        self.meta.push("x");
+        self
+    }
+
+    /// Opaque token echoed back in the response, `O` flag.
+    pub fn opaque(mut self, opaque: Opaque) -> Self {
+        self.meta.push(format!("O{opaque}"));
+        self
+    }
+
+    /// Suppress the `HD` reply on success, `q` flag.
+    pub fn quiet(mut self) -> Self {
+        self.meta.quiet = true;
+        self
+    }
+
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.meta.quiet
+    }
+
+    pub(crate) fn tag(&mut self, token: String) -> Option<String> {
+        self.meta.tag(token)
+    }
+
+    pub(crate) fn prefix(&mut self, prefix: &str) -> crate::Result<()> {
+        self.meta.prefix(prefix)
+    }
+
+    pub(crate) fn key(&self) -> &Key {
+        &self.meta.key
+    }
+
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        self.meta.write(dst, "md", None);
+    }
+}
+
+/// The `ma` command, increments the counter by 1 unless configured otherwise.
+/// Responds with `HD`, or `VA` when the value is requested, `NF` when missing,
+/// `NS` when auto-vivification failed and `EX` on a cas mismatch.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use memento::MetaArithmetic;
+///
+/// let cmd = MetaArithmetic::new("x".parse()?)
+///     .delta(10)
+///     .vivify(Duration::from_secs(60))
+///     .initial(100)
+///     .return_value();
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetaArithmetic {
+    meta: MetaFlags,
+}
+
+impl MetaArithmetic {
+    pub fn new(key: Key) -> Self {
+        Self {
+            meta: MetaFlags::new(key),
+        }
+    }
+
+    /// Key is base64 encoded, `b` flag.
+    pub fn base64(mut self) -> Self {
+        self.meta.push("b");
+        self
+    }
+
+    /// Return the counter value, `v` flag.
+    pub fn return_value(mut self) -> Self {
+        self.meta.push("v");
+        self
+    }
+
+    /// Return the cas unique, `c` flag.
+    pub fn return_cas(mut self) -> Self {
+        self.meta.push("c");
+        self
+    }
+
+    /// Return the remaining TTL in seconds, `t` flag.
+    pub fn return_ttl(mut self) -> Self {
+        self.meta.push("t");
+        self
+    }
+
+    /// Return the key, `k` flag.
+    pub fn return_key(mut self) -> Self {
+        self.meta.push("k");
+        self
+    }
+
+    /// Decrement instead of increment, `MD` flag.
+    pub fn decrement(mut self) -> Self {
+        self.meta.push("MD");
+        self
+    }
+
+    /// Amount to apply, `D` flag.
+    pub fn delta(mut self, delta: u64) -> Self {
+        self.meta.push(format!("D{delta}"));
+        self
+    }
+
+    /// Initial value of an auto-vivified counter, `J` flag.
+    pub fn initial(mut self, initial: u64) -> Self {
+        self.meta.push(format!("J{initial}"));
+        self
+    }
+
+    /// Create the counter with the TTL on a miss, `N` flag.
+    pub fn vivify(mut self, ttl: Duration) -> Self {
+        self.meta.push(format!("N{}", ttl.as_secs()));
+        self
+    }
+
+    /// Update the TTL of the counter, `T` flag.
+    pub fn touch(mut self, ttl: Duration) -> Self {
+        self.meta.push(format!("T{}", ttl.as_secs()));
+        self
+    }
+
+    /// Apply only if the cas unique of the counter matches, `C` flag.
+    pub fn compare_cas(mut self, cas: u64) -> Self {
+        self.meta.push(format!("C{cas}"));
+        self
+    }
+
+    /// Use the token as the new cas unique, `E` flag.
+    pub fn new_cas(mut self, cas: u64) -> Self {
+        self.meta.push(format!("E{cas}"));
+        self
+    }
+
+    /// Opaque token echoed back in the response, `O` flag.
+    pub fn opaque(mut self, opaque: Opaque) -> Self {
+        self.meta.push(format!("O{opaque}"));
+        self
+    }
+
+    /// Suppress the `HD` reply on success, `q` flag.
+    pub fn quiet(mut self) -> Self {
+        self.meta.quiet = true;
+        self
+    }
+
+    pub(crate) fn is_quiet(&self) -> bool {
+        self.meta.quiet
+    }
+
+    pub(crate) fn tag(&mut self, token: String) -> Option<String> {
+        self.meta.tag(token)
+    }
+
+    pub(crate) fn prefix(&mut self, prefix: &str) -> crate::Result<()> {
+        self.meta.prefix(prefix)
+    }
+
+    pub(crate) fn key(&self) -> &Key {
+        &self.meta.key
+    }
+
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        self.meta.write(dst, "ma", None);
+    }
+}
+
+/// The `me` command, responds with `ME` and internal details of the item or `EN` on a miss.
+///
+/// ```rust
+/// use memento::MetaDebug;
+///
+/// let cmd = MetaDebug::new("x".parse()?);
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetaDebug {
+    meta: MetaFlags,
+}
+
+impl MetaDebug {
+    pub fn new(key: Key) -> Self {
+        Self {
+            meta: MetaFlags::new(key),
+        }
+    }
+
+    /// Key is base64 encoded, `b` flag.
+    pub fn base64(mut self) -> Self {
+        self.meta.push("b");
+        self
+    }
+
+    pub(crate) fn prefix(&mut self, prefix: &str) -> crate::Result<()> {
+        self.meta.prefix(prefix)
+    }
+
+    pub(crate) fn key(&self) -> &Key {
+        &self.meta.key
+    }
+
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        self.meta.write(dst, "me", None);
+    }
+}
+
+/// Return code of a meta command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetaStatus {
+    /// `HD`, success without a value.
+    Success,
+    /// `VA`, success with a value.
+    Value,
+    /// `EN`, miss.
+    Miss,
+    /// `NF`, item not found.
+    NotFound,
+    /// `NS`, item not stored.
+    NotStored,
+    /// `EX`, cas mismatch.
+    Exists,
+    /// `MN`, response to `mn`, also returned for a quiet command whose reply was suppressed.
+    Noop,
+    /// `ME`, response to `me`.
+    Debug,
+}
+
+/// Parsed response of a meta command: return code, returned flags and an optional value.
+///
+/// ```rust
+/// use memento::{Frame, MetaResponse, MetaStatus};
+///
+/// let response = MetaResponse::parse(&[
+///     Frame::Line("VA 2 t-1 c42 f3 Oabc W".to_string()),
+///     Frame::Data("hi".into()),
+/// ])?;
+///
+/// assert_eq!(MetaStatus::Value, response.status());
+/// assert_eq!(Some(-1), response.ttl());
+/// assert_eq!(Some(42), response.cas());
+/// assert_eq!(Some(3), response.client_flags());
+/// assert_eq!(Some("abc"), response.opaque());
+/// assert!(response.win());
+/// assert_eq!(Some(&b"hi"[..]), response.value().map(|value| &value[..]));
+/// # Ok::<(), memento::MementoError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetaResponse {
+    status: MetaStatus,
+    flags: Vec<String>,
+    debug: Vec<(String, String)>,
+    value: Option<Bytes>,
+}
+
+impl MetaResponse {
+    /// Builds the response from the frames of a single meta reply.
+    pub fn parse(frames: &[Frame]) -> crate::Result<Self> {
+        let line = frames
+            .first()
+            .and_then(Frame::line)
+            .ok_or_else(|| MementoError::InvalidResponse(format!("{:?}", frames)))?;
+
+        let mut tokens = line.split_whitespace();
+
+        let status = match tokens.next().unwrap_or_default() {
+            "HD" => MetaStatus::Success,
+            "VA" => {
+                tokens.next(); // skip the value size, the data block is already framed.
+
+                MetaStatus::Value
+            }
+            "EN" => MetaStatus::Miss,
+            "NF" => MetaStatus::NotFound,
+            "NS" => MetaStatus::NotStored,
+            "EX" => MetaStatus::Exists,
+            "MN" => MetaStatus::Noop,
+            "ME" => MetaStatus::Debug,
+            _ => return Err(MementoError::InvalidResponse(line.to_string())),
+        };
+
+        let value = match frames.get(1) {
+            Some(Frame::Data(data)) => Some(data.clone()),
+            _ => None,
+        };
+
+        // `ME` returns `<name>=<value>` pairs instead of flags, `c=...` is not a cas.
+        let (flags, debug) = match status {
+            MetaStatus::Debug => (
+                Vec::new(),
+                tokens
+                    .filter_map(|token| token.split_once('='))
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            ),
+            _ => (tokens.map(ToString::to_string).collect(), Vec::new()),
+        };
+
+        Ok(Self {
+            status,
+            flags,
+            debug,
+            value,
+        })
+    }
+
+    pub fn status(&self) -> MetaStatus {
+        self.status
+    }
+
+    /// Value returned by `VA`.
+    pub fn value(&self) -> Option<&Bytes> {
+        self.value.as_ref()
+    }
+
+    /// Token of the returned flag, e.g. `Some("42")` for `c42` and `Some("")` for `W`.
+    pub fn flag(&self, flag: char) -> Option<&str> {
+        self.flags
+            .iter()
+            .find(|token| token.starts_with(flag))
+            .map(|token| &token[flag.len_utf8()..])
+    }
+
+    /// Remaining TTL in seconds, `-1` for unlimited.
+    pub fn ttl(&self) -> Option<i64> {
+        self.flag('t').and_then(|token| token.parse().ok())
+    }
+
+    pub fn cas(&self) -> Option<u64> {
+        self.flag('c').and_then(|token| token.parse().ok())
+    }
+
+    pub fn client_flags(&self) -> Option<u32> {
+        self.flag('f').and_then(|token| token.parse().ok())
+    }
+
+    pub fn size(&self) -> Option<usize> {
+        self.flag('s').and_then(|token| token.parse().ok())
+    }
+
+    /// Seconds since the item was last accessed.
+    pub fn last_access(&self) -> Option<u64> {
+        self.flag('l').and_then(|token| token.parse().ok())
+    }
+
+    /// Whether the item has been hit before.
+    pub fn hit(&self) -> Option<bool> {
+        self.flag('h').map(|token| token == "1")
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.flag('k')
+    }
+
+    pub fn opaque(&self) -> Option<&str> {
+        self.flag('O')
+    }
+
+    /// Removes the opaque token added by the client itself.
+    pub(crate) fn untag(&mut self) {
+        self.flags.retain(|token| !token.starts_with('O'));
+    }
+
+    /// The client won the right to recache the item.
+    pub fn win(&self) -> bool {
+        self.flag('W').is_some()
+    }
+
+    /// The item is stale.
+    pub fn stale(&self) -> bool {
+        self.flag('X').is_some()
+    }
+
+    /// Another client already won the right to recache the item.
+    pub fn win_sent(&self) -> bool {
+        self.flag('Z').is_some()
+    }
+
+    /// `<name>=<value>` pairs returned by `me`, kept apart from the flags.
+    ///
+    /// ```rust
+    /// use memento::{Frame, MetaResponse};
+    ///
+    /// let response = MetaResponse::parse(&[Frame::Line(
+    ///     "ME x exp=-1 la=3 cas=2 fetch=no cls=1 size=63".to_string(),
+    /// )])?;
+    ///
+    /// assert_eq!(Some(("cas", "2")), response.debug().get(2).copied());
+    /// assert_eq!(None, response.cas());
+    /// assert_eq!(None, response.client_flags());
+    /// # Ok::<(), memento::MementoError>(())
+    /// ```
+    pub fn debug(&self) -> Vec<(&str, &str)> {
+        self.debug
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    Add, Append, Cas, Command, CommandResp, Decr, Delete, Gat, Incr, Item, Key, Memento, Prepend,
+    Replace, Set, Timeouts, Touch,
+};
+use std::time::Duration;
+
+/// Batch of commands written to the connection with a single flush, the replies are read
+/// in the order the commands were added, so the whole batch costs one round trip.
+///
+/// ```rust,no_run
+/// use memento::{CommandResp, Item};
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let mut memento = memento::new("localhost:11211").await?;
+///
+///     let responses = memento
+///         .pipeline()
+///         .set("x".parse()?, Item::timeless("y"))
+///         .get("x".parse()?)
+///         .incr("counter".parse()?, 1)
+///         .execute()
+///         .await;
+///
+///     for response in responses {
+///         match response? {
+///             CommandResp::Stored => println!("stored"),
+///             resp => println!("{:#?}", resp),
+///         }
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Pipeline<'a> {
+    memento: &'a mut Memento,
+    commands: Vec<Command>,
+    timeouts: Option<Timeouts>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub(crate) fn new(memento: &'a mut Memento) -> Self {
+        Self {
+            memento,
+            commands: Vec::new(),
+            timeouts: None,
+        }
+    }
+
+    /// Adds any command, including the ones built with `noreply()`.
+    pub fn command(mut self, cmd: Command) -> Self {
+        self.commands.push(cmd);
+        self
+    }
+
+    pub fn set(self, key: Key, item: Item) -> Self {
+        self.command(Command::Set(Set::new(key, item)))
+    }
+
+    pub fn add(self, key: Key, item: Item) -> Self {
+        self.command(Command::Add(Add::new(key, item)))
+    }
+
+    pub fn append(self, key: Key, item: Item) -> Self {
+        self.command(Command::Append(Append::new(key, item)))
+    }
+
+    pub fn prepend(self, key: Key, item: Item) -> Self {
+        self.command(Command::Prepend(Prepend::new(key, item)))
+    }
+
+    pub fn replace(self, key: Key, item: Item) -> Self {
+        self.command(Command::Replace(Replace::new(key, item)))
+    }
+
+    pub fn cas(self, key: Key, item: Item, cas: u64) -> Self {
+        self.command(Command::Cas(Cas::new(key, item, cas)))
+    }
+
+    pub fn get(self, key: Key) -> Self {
+        self.command(Command::Get(key))
+    }
+
+    pub fn gets(self, keys: Vec<Key>) -> Self {
+        self.command(Command::Gets(keys))
+    }
+
+    pub fn incr(self, key: Key, value: u64) -> Self {
+        self.command(Command::Incr(Incr::new(key, value)))
+    }
+
+    pub fn decr(self, key: Key, value: u64) -> Self {
+        self.command(Command::Decr(Decr::new(key, value)))
+    }
+
+    pub fn delete(self, key: Key) -> Self {
+        self.command(Command::Delete(Delete::new(key)))
+    }
+
+    pub fn touch(self, key: Key, ttl: Duration) -> Self {
+        self.command(Command::Touch(Touch::new(key, ttl)))
+    }
+
+    pub fn gat(self, ttl: Duration, keys: Vec<Key>) -> Self {
+        self.command(Command::Gat(Gat::new(ttl, keys)))
+    }
+
+    /// Overrides the timeouts of the batch, the read timeout applies to every reply.
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Sends the batch and returns a response per command. A command rejected by the server
+    /// fails alone, while a broken connection fails all commands that were not answered yet.
+    pub async fn execute(self) -> Vec<crate::Result<CommandResp>> {
+        if self.commands.is_empty() {
+            return Vec::new();
+        }
+
+        if let Some(timeouts) = self.timeouts {
+            self.memento.with_timeouts(timeouts);
+        }
+
+        self.memento.call_many(self.commands).await
+    }
+}
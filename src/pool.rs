@@ -0,0 +1,559 @@
+use crate::{Address, Memento, MementoBuilder, MementoError};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::time::{Duration, Instant};
+use tokio::net::lookup_host;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Period of the background task that closes expired connections when no timeout is configured.
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Configures `MementoPool`.
+///
+/// ```rust,no_run
+/// use memento::{MementoPool, Protocol};
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let pool = MementoPool::builder()
+///         .memento(memento::Memento::builder().protocol(Protocol::Binary))
+///         .min_connections(2)
+///         .max_connections(16)
+///         .idle_timeout(Duration::from_secs(60))
+///         .max_lifetime(None)
+///         .checkout_timeout(Duration::from_secs(1))
+///         .health_check(true)
+///         .connect("localhost:11211")
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoolBuilder {
+    memento: MementoBuilder,
+    min_connections: usize,
+    max_connections: usize,
+    idle_timeout: Option<Duration>,
+    max_lifetime: Option<Duration>,
+    checkout_timeout: Option<Duration>,
+    health_check: bool,
+}
+
+impl Default for PoolBuilder {
+    fn default() -> Self {
+        Self {
+            memento: MementoBuilder::default(),
+            min_connections: 0,
+            max_connections: 10,
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+            checkout_timeout: Some(Duration::from_secs(30)),
+            health_check: false,
+        }
+    }
+}
+
+impl PoolBuilder {
+    /// Settings of every connection opened by the pool.
+    pub fn memento(mut self, memento: MementoBuilder) -> Self {
+        self.memento = memento;
+        self
+    }
+
+    /// Connections opened on start and kept open even when idle, 0 by default.
+    pub fn min_connections(mut self, min: usize) -> Self {
+        self.min_connections = min;
+        self
+    }
+
+    /// Upper bound of connections open at once, 10 by default.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = max.max(1);
+        self
+    }
+
+    /// Idle connections above `min_connections` are closed after this time, 10 minutes by default.
+    pub fn idle_timeout<T: Into<Option<Duration>>>(mut self, timeout: T) -> Self {
+        self.idle_timeout = timeout.into();
+        self
+    }
+
+    /// Connections are closed once they are older than this, 30 minutes by default.
+    pub fn max_lifetime<T: Into<Option<Duration>>>(mut self, lifetime: T) -> Self {
+        self.max_lifetime = lifetime.into();
+        self
+    }
+
+    /// How long `MementoPool::get` waits for a connection, 30 seconds by default.
+    pub fn checkout_timeout<T: Into<Option<Duration>>>(mut self, timeout: T) -> Self {
+        self.checkout_timeout = timeout.into();
+        self
+    }
+
+    /// Send `version` before handing out an idle connection and replace it if the check fails.
+    ///
+    /// ```rust
+    /// use memento::MementoPool;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let addr = listener.local_addr()?;
+    ///     let accepted = Arc::new(AtomicUsize::new(0));
+    ///
+    ///     tokio::spawn({
+    ///         let accepted = accepted.clone();
+    ///
+    ///         async move {
+    ///             while let Ok((stream, _)) = listener.accept().await {
+    ///                 // the first connection is closed right away.
+    ///                 if accepted.fetch_add(1, Ordering::Relaxed) == 0 {
+    ///                     continue;
+    ///                 }
+    ///
+    ///                 tokio::spawn(async move {
+    ///                     let mut stream = BufReader::new(stream);
+    ///                     let mut line = String::new();
+    ///
+    ///                     while stream.read_line(&mut line).await? > 0 {
+    ///                         stream.write_all(b"VERSION 1.6.21\r\n").await?;
+    ///                         line.clear();
+    ///                     }
+    ///
+    ///                     Ok::<_, std::io::Error>(())
+    ///                 });
+    ///             }
+    ///         }
+    ///     });
+    ///
+    ///     let pool = MementoPool::builder().health_check(true).connect(addr).await?;
+    ///
+    ///     drop(pool.get().await?);
+    ///
+    ///     assert_eq!(1, pool.idle());
+    ///
+    ///     // the closed idle connection fails the check and a new one is opened instead.
+    ///     let mut memento = pool.get().await?;
+    ///
+    ///     assert!(memento.version().await.is_ok());
+    ///     assert_eq!(2, accepted.load(Ordering::Relaxed));
+    ///     assert_eq!(1, pool.size());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn health_check(mut self, enabled: bool) -> Self {
+        self.health_check = enabled;
+        self
+    }
+
+    /// Resolves the address, opens `min_connections` and starts the task closing
+    /// expired connections.
+    pub async fn connect<A: Address>(self, addr: A) -> crate::Result<MementoPool> {
+        let shared = Arc::new(Shared {
+            host: addr.host().map(ToString::to_string),
+            addrs: lookup_host(addr).await?.collect(),
+            semaphore: Arc::new(Semaphore::new(self.max_connections)),
+            idle: Mutex::new(VecDeque::new()),
+            size: Arc::new(AtomicUsize::new(0)),
+            min_connections: self.min_connections.min(self.max_connections),
+            builder: self,
+        });
+
+        shared.fill().await?;
+
+        let period = [shared.builder.idle_timeout, shared.builder.max_lifetime]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(REAP_INTERVAL)
+            .max(Duration::from_secs(1));
+
+        tokio::spawn(reap(Arc::downgrade(&shared), period));
+
+        Ok(MementoPool { shared })
+    }
+}
+
+/// Pool of connections to a single server shared by concurrent tasks.
+///
+/// ```rust,no_run
+/// use memento::{Item, MementoPool};
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let pool = MementoPool::connect("localhost:11211").await?;
+///
+///     let mut tasks = Vec::new();
+///
+///     for i in 0..8 {
+///         let pool = pool.clone();
+///
+///         tasks.push(tokio::spawn(async move {
+///             let mut memento = pool.get().await?;
+///
+///             memento.set(format!("x{i}").parse()?, Item::timeless(i)).await
+///         }));
+///     }
+///
+///     for task in tasks {
+///         println!("{:?}", task.await);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MementoPool {
+    shared: Arc<Shared>,
+}
+
+impl MementoPool {
+    pub fn builder() -> PoolBuilder {
+        PoolBuilder::default()
+    }
+
+    /// Pool with the default settings.
+    pub async fn connect<A: Address>(addr: A) -> crate::Result<Self> {
+        PoolBuilder::default().connect(addr).await
+    }
+
+    /// Hands out an idle connection or opens a new one, waits for a connection to be returned
+    /// when `max_connections` are in use and fails with `MementoError::PoolTimeout`
+    /// if nothing is available within the checkout timeout.
+    ///
+    /// ```rust
+    /// use memento::{MementoError, MementoPool};
+    /// use std::time::Duration;
+    /// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    /// use tokio::net::TcpListener;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> memento::Result<()> {
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let addr = listener.local_addr()?;
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok((stream, _)) = listener.accept().await {
+    ///             tokio::spawn(async move {
+    ///                 let mut stream = BufReader::new(stream);
+    ///                 let mut line = String::new();
+    ///
+    ///                 // only `version` is answered.
+    ///                 while stream.read_line(&mut line).await? > 0 {
+    ///                     if line == "version\r\n" {
+    ///                         stream.write_all(b"VERSION 1.6.21\r\n").await?;
+    ///                     }
+    ///
+    ///                     line.clear();
+    ///                 }
+    ///
+    ///                 Ok::<_, std::io::Error>(())
+    ///             });
+    ///         }
+    ///     });
+    ///
+    ///     let pool = MementoPool::builder()
+    ///         .max_connections(2)
+    ///         .checkout_timeout(Duration::from_millis(100))
+    ///         .connect(addr)
+    ///         .await?;
+    ///
+    ///     let first = pool.get().await?;
+    ///     let second = pool.get().await?;
+    ///
+    ///     assert_eq!((2, 0), (pool.size(), pool.idle()));
+    ///     assert!(matches!(pool.get().await, Err(MementoError::PoolTimeout)));
+    ///
+    ///     // a returned connection is handed out again.
+    ///     drop(first);
+    ///
+    ///     assert_eq!((2, 1), (pool.size(), pool.idle()));
+    ///
+    ///     let mut third = pool.get().await?;
+    ///
+    ///     assert_eq!((2, 0), (pool.size(), pool.idle()));
+    ///     assert!(third.version().await.is_ok());
+    ///
+    ///     // a waiting checkout gets the connection released meanwhile.
+    ///     let waiting = tokio::spawn({
+    ///         let pool = pool.clone();
+    ///
+    ///         async move { pool.get().await.map(|_| ()) }
+    ///     });
+    ///
+    ///     drop(second);
+    ///
+    ///     assert!(matches!(waiting.await, Ok(Ok(()))));
+    ///     assert_eq!(2, pool.size());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get(&self) -> crate::Result<PooledMemento> {
+        match self.shared.builder.checkout_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.checkout())
+                .await
+                .map_err(|_| MementoError::PoolTimeout)?,
+            None => self.checkout().await,
+        }
+    }
+
+    /// Connections currently open, both idle and checked out.
+    pub fn size(&self) -> usize {
+        self.shared.size.load(Ordering::Relaxed)
+    }
+
+    /// Connections waiting in the pool.
+    pub fn idle(&self) -> usize {
+        self.shared.idle().len()
+    }
+
+    async fn checkout(&self) -> crate::Result<PooledMemento> {
+        let permit = self
+            .shared
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| MementoError::PoolTimeout)?;
+
+        loop {
+            let idle = self.shared.idle().pop_back();
+
+            let connection = match idle {
+                Some(connection) if self.shared.is_expired(&connection) => continue,
+                Some(mut connection) if self.shared.builder.health_check => {
+                    match connection.memento.version().await {
+                        Ok(..) if !connection.memento.is_broken() => connection,
+                        _ => continue,
+                    }
+                }
+                Some(connection) => connection,
+                None => self.shared.open().await?,
+            };
+
+            return Ok(PooledMemento {
+                connection: Some(connection),
+                shared: self.shared.clone(),
+                _permit: permit,
+            });
+        }
+    }
+}
+
+/// Connection checked out of `MementoPool`, goes back to the pool when dropped
+/// unless the last call left it in the middle of a response.
+///
+/// ```rust
+/// use memento::{Memento, MementoError, MementoPool};
+/// use std::time::Duration;
+/// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+/// use tokio::net::TcpListener;
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+///     let addr = listener.local_addr()?;
+///
+///     tokio::spawn(async move {
+///         while let Ok((stream, _)) = listener.accept().await {
+///             tokio::spawn(async move {
+///                 let mut stream = BufReader::new(stream);
+///                 let mut line = String::new();
+///
+///                 // only `version` is answered.
+///                 while stream.read_line(&mut line).await? > 0 {
+///                     if line == "version\r\n" {
+///                         stream.write_all(b"VERSION 1.6.21\r\n").await?;
+///                     }
+///
+///                     line.clear();
+///                 }
+///
+///                 Ok::<_, std::io::Error>(())
+///             });
+///         }
+///     });
+///
+///     let pool = MementoPool::builder()
+///         .memento(Memento::builder().read_timeout(Duration::from_millis(50)))
+///         .connect(addr)
+///         .await?;
+///
+///     let mut memento = pool.get().await?;
+///
+///     assert!(memento.version().await.is_ok());
+///     drop(memento);
+///
+///     assert_eq!((1, 1), (pool.size(), pool.idle()));
+///
+///     // the reply to `get` may still arrive, so the connection is closed instead.
+///     let mut memento = pool.get().await?;
+///
+///     assert!(matches!(memento.get("x".parse()?).await, Err(MementoError::Timeout)));
+///     drop(memento);
+///
+///     assert_eq!((0, 0), (pool.size(), pool.idle()));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PooledMemento {
+    connection: Option<Pooled>,
+    shared: Arc<Shared>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for PooledMemento {
+    type Target = Memento;
+
+    fn deref(&self) -> &Self::Target {
+        &self
+            .connection
+            .as_ref()
+            .expect("connection is taken on drop")
+            .memento
+    }
+}
+
+impl DerefMut for PooledMemento {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self
+            .connection
+            .as_mut()
+            .expect("connection is taken on drop")
+            .memento
+    }
+}
+
+impl Drop for PooledMemento {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            if !connection.memento.is_broken() && !self.shared.is_expired(&connection) {
+                self.shared.release(connection);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    builder: PoolBuilder,
+    addrs: Vec<SocketAddr>,
+    /// Host of the address, the addresses are resolved once.
+    host: Option<String>,
+    min_connections: usize,
+    semaphore: Arc<Semaphore>,
+    idle: Mutex<VecDeque<Pooled>>,
+    size: Arc<AtomicUsize>,
+}
+
+impl Shared {
+    fn idle(&self) -> MutexGuard<'_, VecDeque<Pooled>> {
+        self.idle.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    async fn open(&self) -> crate::Result<Pooled> {
+        let memento = self
+            .builder
+            .memento
+            .clone()
+            .connect_host(&self.addrs[..], self.host.as_deref())
+            .await?;
+        let now = Instant::now();
+
+        self.size.fetch_add(1, Ordering::Relaxed);
+
+        Ok(Pooled {
+            memento,
+            created: now,
+            idle_since: now,
+            size: self.size.clone(),
+        })
+    }
+
+    fn release(&self, mut connection: Pooled) {
+        connection.idle_since = Instant::now();
+
+        self.idle().push_back(connection);
+    }
+
+    fn is_expired(&self, connection: &Pooled) -> bool {
+        self.builder
+            .max_lifetime
+            .is_some_and(|lifetime| connection.created.elapsed() >= lifetime)
+    }
+
+    /// Closes connections older than `max_lifetime` and the ones idle for longer than
+    /// `idle_timeout` while there are more than `min_connections` open.
+    fn reap(&self) {
+        let mut idle = self.idle();
+
+        idle.retain(|connection| !self.is_expired(connection));
+
+        // the oldest released connections are at the front.
+        while self.size.load(Ordering::Relaxed) > self.min_connections
+            && idle.front().is_some_and(|connection| {
+                self.builder
+                    .idle_timeout
+                    .is_some_and(|timeout| connection.idle_since.elapsed() >= timeout)
+            })
+        {
+            idle.pop_front();
+        }
+    }
+
+    /// Opens connections until there are `min_connections`.
+    async fn fill(&self) -> crate::Result<()> {
+        while self.size.load(Ordering::Relaxed) < self.min_connections {
+            let connection = self.open().await?;
+
+            self.release(connection);
+        }
+
+        Ok(())
+    }
+}
+
+async fn reap(shared: Weak<Shared>, period: Duration) {
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+
+        shared.reap();
+
+        // a failed connection is retried on the next tick.
+        let _ = shared.fill().await;
+    }
+}
+
+/// Pooled connection, the pool size is decreased when it is closed.
+#[derive(Debug)]
+struct Pooled {
+    memento: Memento,
+    created: Instant,
+    idle_since: Instant,
+    size: Arc<AtomicUsize>,
+}
+
+impl Drop for Pooled {
+    fn drop(&mut self) {
+        self.size.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,61 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use std::fmt::{Debug, Formatter};
+
+/// The only SASL mechanism supported by the client.
+pub(crate) const PLAIN: &str = "PLAIN";
+
+/// Username and password used to authenticate with the `PLAIN` SASL mechanism.
+///
+/// ```rust
+/// use memento::Credentials;
+///
+/// let credentials = Credentials::new("user", "secret");
+///
+/// assert_eq!("user", credentials.username());
+/// assert_eq!(r#"Credentials { username: "user", password: "***" }"#, format!("{:?}", credentials));
+/// ```
+#[derive(Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    pub fn new<U: Into<String>, P: Into<String>>(username: U, password: P) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// `PLAIN` message as described in RFC 4616: `\0<username>\0<password>`, the authorization
+    /// identity is left empty.
+    pub(crate) fn plain(&self) -> Bytes {
+        let mut message = BytesMut::with_capacity(self.username.len() + self.password.len() + 2);
+
+        message.put_u8(0);
+        message.put(self.username.as_bytes());
+        message.put_u8(0);
+        message.put(self.password.as_bytes());
+
+        message.freeze()
+    }
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
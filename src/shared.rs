@@ -0,0 +1,343 @@
+use crate::{
+    Add, Append, Cas, Command, CommandResp, Decr, Delete, FlushAll, Gat, Incr, Item, Key, Memento,
+    MementoError, MetaArithmetic, MetaDelete, MetaGet, MetaResponse, MetaSet, Prepend, Replace,
+    Set, Touch,
+};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Requests waiting for the connection task, callers wait once the queue is full.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Requests written to the connection at once.
+const MAX_BATCH: usize = 128;
+
+struct Request {
+    cmd: Command,
+    reply: oneshot::Sender<crate::Result<CommandResp>>,
+}
+
+/// Client that can be cloned and shared between tasks, all clones use a single connection.
+///
+/// The connection is owned by a background task: requests queued by the clones are written
+/// in batches with a single flush and the replies are routed back in order. Meta commands
+/// without an opaque are tagged with one which is checked against the reply when the server
+/// echoes it, a mismatch fails the request and makes the task reconnect. A broken
+/// connection is reopened before the next batch. The task stops when the last clone
+/// is dropped.
+///
+/// ```rust,no_run
+/// use memento::{Item, SharedMemento};
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let memento = SharedMemento::new(memento::new("localhost:11211").await?);
+///
+///     let mut tasks = Vec::new();
+///
+///     for i in 0..8 {
+///         let memento = memento.clone();
+///
+///         tasks.push(tokio::spawn(async move {
+///             memento.set(format!("x{i}").parse()?, Item::timeless(i)).await
+///         }));
+///     }
+///
+///     for task in tasks {
+///         println!("{:?}", task.await);
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// Calls of many clones at once share the batches, each still gets its own reply.
+///
+/// ```rust
+/// use memento::{CommandResp, SharedMemento};
+/// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+/// use tokio::net::TcpListener;
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+///     let addr = listener.local_addr()?;
+///
+///     tokio::spawn(async move {
+///         let (stream, _) = listener.accept().await?;
+///         let mut stream = BufReader::new(stream);
+///         let mut line = String::new();
+///
+///         // `get <key>` is answered with the value `<key>-value`.
+///         while stream.read_line(&mut line).await? > 0 {
+///             let key = line.trim_end().trim_start_matches("get ");
+///             let value = format!("{key}-value");
+///
+///             let reply = format!("VALUE {key} 0 {}\r\n{value}\r\nEND\r\n", value.len());
+///
+///             stream.write_all(reply.as_bytes()).await?;
+///             line.clear();
+///         }
+///
+///         Ok::<_, std::io::Error>(())
+///     });
+///
+///     let memento = SharedMemento::new(memento::new(addr).await?);
+///     let mut tasks = Vec::new();
+///
+///     for i in 0..64 {
+///         let memento = memento.clone();
+///
+///         let key = format!("x{i}").parse()?;
+///
+///         tasks.push(tokio::spawn(async move { (i, memento.get(key).await) }));
+///     }
+///
+///     for task in tasks {
+///         let (i, response) = task.await.expect("task panicked");
+///
+///         assert!(matches!(
+///             response?,
+///             CommandResp::Value { key, item } if key.to_string() == format!("x{i}")
+///                 && item.value().as_ref() == format!("x{i}-value").as_bytes()
+///         ));
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// A reply echoing another opaque fails the request and the connection is reopened.
+///
+/// ```rust
+/// use memento::{MementoError, MetaGet, MetaStatus, SharedMemento};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+/// use tokio::net::TcpListener;
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+///     let addr = listener.local_addr()?;
+///     let accepted = Arc::new(AtomicUsize::new(0));
+///
+///     tokio::spawn({
+///         let accepted = accepted.clone();
+///
+///         async move {
+///             while let Ok((stream, _)) = listener.accept().await {
+///                 let first = accepted.fetch_add(1, Ordering::Relaxed) == 0;
+///
+///                 tokio::spawn(async move {
+///                     let mut stream = BufReader::new(stream);
+///                     let mut line = String::new();
+///
+///                     // the first connection answers with a stale opaque, the others echo it.
+///                     while stream.read_line(&mut line).await? > 0 {
+///                         let echo = line.split_whitespace().find(|flag| flag.starts_with('O'));
+///                         let opaque = if first { "O0" } else { echo.unwrap_or_default() };
+///
+///                         stream.write_all(format!("HD {opaque}\r\n").as_bytes()).await?;
+///                         line.clear();
+///                     }
+///
+///                     Ok::<_, std::io::Error>(())
+///                 });
+///             }
+///         }
+///     });
+///
+///     let memento = SharedMemento::new(memento::new(addr).await?);
+///
+///     let result = memento.meta_get(MetaGet::new("x".parse()?)).await;
+///
+///     assert!(matches!(result, Err(MementoError::InvalidResponse(..))));
+///
+///     let response = memento.meta_get(MetaGet::new("x".parse()?)).await?;
+///
+///     assert!(matches!(response.status(), MetaStatus::Success));
+///     assert_eq!(None, response.opaque());
+///     assert_eq!(2, accepted.load(Ordering::Relaxed));
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedMemento {
+    sender: mpsc::Sender<Request>,
+}
+
+impl SharedMemento {
+    /// Moves the client into a background task, must be called within the tokio runtime.
+    pub fn new(memento: Memento) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+
+        tokio::spawn(run(memento, receiver));
+
+        Self { sender }
+    }
+
+    pub async fn set(&self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.call(Command::Set(Set::new(key, item))).await
+    }
+
+    pub async fn add(&self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.call(Command::Add(Add::new(key, item))).await
+    }
+
+    pub async fn append(&self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.call(Command::Append(Append::new(key, item))).await
+    }
+
+    pub async fn prepend(&self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.call(Command::Prepend(Prepend::new(key, item))).await
+    }
+
+    pub async fn replace(&self, key: Key, item: Item) -> crate::Result<CommandResp> {
+        self.call(Command::Replace(Replace::new(key, item))).await
+    }
+
+    pub async fn cas(&self, key: Key, item: Item, cas: u64) -> crate::Result<CommandResp> {
+        self.call(Command::Cas(Cas::new(key, item, cas))).await
+    }
+
+    pub async fn get(&self, key: Key) -> crate::Result<CommandResp> {
+        self.call(Command::Get(key)).await
+    }
+
+    pub async fn gets(&self, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        self.call(Command::Gets(keys)).await
+    }
+
+    pub async fn incr(&self, key: Key, value: u64) -> crate::Result<CommandResp> {
+        self.call(Command::Incr(Incr::new(key, value))).await
+    }
+
+    pub async fn decr(&self, key: Key, value: u64) -> crate::Result<CommandResp> {
+        self.call(Command::Decr(Decr::new(key, value))).await
+    }
+
+    pub async fn delete(&self, key: Key) -> crate::Result<CommandResp> {
+        self.call(Command::Delete(Delete::new(key))).await
+    }
+
+    pub async fn touch(&self, key: Key, ttl: Duration) -> crate::Result<CommandResp> {
+        self.call(Command::Touch(Touch::new(key, ttl))).await
+    }
+
+    pub async fn gat(&self, ttl: Duration, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        self.call(Command::Gat(Gat::new(ttl, keys))).await
+    }
+
+    pub async fn gats(&self, ttl: Duration, keys: Vec<Key>) -> crate::Result<CommandResp> {
+        self.call(Command::Gats(Gat::new(ttl, keys))).await
+    }
+
+    pub async fn flush_all(&self, delay: Option<Duration>) -> crate::Result<CommandResp> {
+        self.call(Command::FlushAll(FlushAll::new(delay))).await
+    }
+
+    pub async fn meta_get(&self, cmd: MetaGet) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaGet(cmd)).await
+    }
+
+    pub async fn meta_set(&self, cmd: MetaSet) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaSet(cmd)).await
+    }
+
+    pub async fn meta_delete(&self, cmd: MetaDelete) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaDelete(cmd)).await
+    }
+
+    pub async fn meta_arithmetic(&self, cmd: MetaArithmetic) -> crate::Result<MetaResponse> {
+        self.meta(Command::MetaArithmetic(cmd)).await
+    }
+
+    async fn meta(&self, cmd: Command) -> crate::Result<MetaResponse> {
+        match self.call(cmd).await? {
+            CommandResp::Meta(response) => Ok(response),
+            resp => Err(MementoError::InvalidResponse(format!("{:?}", resp))),
+        }
+    }
+
+    pub async fn version(&self) -> crate::Result<CommandResp> {
+        self.call(Command::Version).await
+    }
+
+    pub async fn stats(&self) -> crate::Result<CommandResp> {
+        self.call(Command::Stats).await
+    }
+
+    /// Queues the command and waits for its reply, fails with `MementoError::ConnectionReset`
+    /// if the connection task is gone.
+    pub async fn call(&self, cmd: Command) -> crate::Result<CommandResp> {
+        let (reply, response) = oneshot::channel();
+
+        self.sender
+            .send(Request { cmd, reply })
+            .await
+            .map_err(|_| MementoError::ConnectionReset)?;
+
+        response.await.map_err(|_| MementoError::ConnectionReset)?
+    }
+}
+
+async fn run(mut memento: Memento, mut receiver: mpsc::Receiver<Request>) {
+    let mut requests = Vec::with_capacity(MAX_BATCH);
+    let mut token: u64 = 0;
+
+    while receiver.recv_many(&mut requests, MAX_BATCH).await > 0 {
+        if memento.is_broken() {
+            if let Err(err) = memento.reconnect().await {
+                for request in requests.drain(..) {
+                    let _ = request.reply.send(Err(err.clone()));
+                }
+
+                continue;
+            }
+        }
+
+        let mut cmds = Vec::with_capacity(requests.len());
+        let mut replies = Vec::with_capacity(requests.len());
+
+        for Request { mut cmd, reply } in requests.drain(..) {
+            token = token.wrapping_add(1);
+
+            replies.push((cmd.tag(token.to_string()), reply));
+            cmds.push(cmd);
+        }
+
+        let responses = memento.call_many::<CommandResp>(cmds).await;
+        let mut desynced = false;
+
+        for ((opaque, reply), response) in replies.into_iter().zip(responses) {
+            let response = match (response, opaque) {
+                (Ok(CommandResp::Meta(meta)), Some(opaque))
+                    if meta.opaque().is_some_and(|token| token != opaque) =>
+                {
+                    desynced = true;
+
+                    Err(MementoError::InvalidResponse(format!(
+                        "reply {:?} does not match opaque {opaque}",
+                        meta
+                    )))
+                }
+                (Ok(CommandResp::Meta(mut meta)), Some(..)) => {
+                    meta.untag();
+
+                    Ok(CommandResp::Meta(meta))
+                }
+                (response, _) => response,
+            };
+
+            // the caller may be gone already.
+            let _ = reply.send(response);
+        }
+
+        if desynced {
+            // failure leaves the connection broken, so the next batch retries.
+            let _ = memento.reconnect().await;
+        }
+    }
+}
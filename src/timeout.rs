@@ -0,0 +1,153 @@
+use crate::MementoError;
+use std::future::Future;
+use std::time::Duration;
+
+/// Limits of the network operations, `None` waits forever.
+///
+/// The read timeout applies to every reply, so a pipeline of many commands may take
+/// longer than the timeout as a whole.
+///
+/// ```rust
+/// use memento::Timeouts;
+/// use std::time::Duration;
+///
+/// let timeouts = Timeouts::default()
+///     .connect(Duration::from_secs(1))
+///     .read(Duration::from_millis(100));
+///
+/// assert_eq!(Some(Duration::from_secs(1)), timeouts.connect_timeout());
+/// assert_eq!(None, timeouts.write_timeout());
+///
+/// let call = Timeouts::default().read(Duration::from_millis(10)).or(timeouts);
+///
+/// assert_eq!(Some(Duration::from_millis(10)), call.read_timeout());
+/// assert_eq!(Some(Duration::from_secs(1)), call.connect_timeout());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timeouts {
+    connect: Option<Duration>,
+    write: Option<Duration>,
+    read: Option<Duration>,
+}
+
+impl Timeouts {
+    /// Establishing the connection including SASL authentication.
+    pub fn connect(mut self, timeout: Duration) -> Self {
+        self.connect = Some(timeout);
+        self
+    }
+
+    /// Writing and flushing the commands.
+    pub fn write(mut self, timeout: Duration) -> Self {
+        self.write = Some(timeout);
+        self
+    }
+
+    /// Reading a reply.
+    pub fn read(mut self, timeout: Duration) -> Self {
+        self.read = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect
+    }
+
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read
+    }
+
+    /// Timeouts that are not set are taken from `other`.
+    pub fn or(self, other: Timeouts) -> Self {
+        Self {
+            connect: self.connect.or(other.connect),
+            write: self.write.or(other.write),
+            read: self.read.or(other.read),
+        }
+    }
+}
+
+/// Fails with `MementoError::Timeout` if the future is not completed in time.
+pub(crate) async fn timeout<T, F>(duration: Option<Duration>, future: F) -> crate::Result<T>
+where
+    F: Future<Output = crate::Result<T>>,
+{
+    match duration {
+        Some(duration) => tokio::time::timeout(duration, future)
+            .await
+            .map_err(|_| MementoError::Timeout)?,
+        None => future.await,
+    }
+}
+
+/// Durations of configuration files: `"150ms"`, `"2s"`, `"1m"`, `"1h"` or a number of milliseconds.
+#[cfg(feature = "serde")]
+pub(crate) mod duration {
+    use serde::de::{Error, Unexpected, Visitor};
+    use serde::Deserializer;
+    use std::fmt::Formatter;
+    use std::time::Duration;
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        deserializer.deserialize_any(DurationVisitor)
+    }
+
+    pub(crate) fn option<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        deserialize(deserializer).map(Some)
+    }
+
+    struct DurationVisitor;
+
+    impl Visitor<'_> for DurationVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+            write!(
+                f,
+                "a duration like \"150ms\", \"2s\", \"1m\", \"1h\" or milliseconds"
+            )
+        }
+
+        fn visit_u64<E: Error>(self, millis: u64) -> Result<Duration, E> {
+            Ok(Duration::from_millis(millis))
+        }
+
+        fn visit_i64<E: Error>(self, millis: i64) -> Result<Duration, E> {
+            u64::try_from(millis)
+                .map(Duration::from_millis)
+                .map_err(|_| E::invalid_value(Unexpected::Signed(millis), &self))
+        }
+
+        fn visit_str<E: Error>(self, value: &str) -> Result<Duration, E> {
+            let split = value
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(value.len());
+
+            let (amount, unit) = value.split_at(split);
+
+            let amount = amount
+                .parse::<u64>()
+                .map_err(|_| E::invalid_value(Unexpected::Str(value), &self))?;
+
+            let seconds = match unit.trim() {
+                "ms" | "" => return Ok(Duration::from_millis(amount)),
+                "s" => Some(amount),
+                "m" => amount.checked_mul(60),
+                "h" => amount.checked_mul(60 * 60),
+                _ => None,
+            };
+
+            seconds
+                .map(Duration::from_secs)
+                .ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+        }
+    }
+}
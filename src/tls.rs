@@ -0,0 +1,268 @@
+use crate::MementoError;
+use std::fmt::{Debug, Formatter};
+use std::io::Error;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+/// TLS settings of the connection, available with the `tls` feature.
+///
+/// The server certificate is verified against the given CA roots only, so a self-signed
+/// server works once its certificate is added as a root, as long as the certificate is not
+/// marked as a CA itself. The name sent as SNI and checked against the certificate is
+/// the host of the address passed to `connect`, or the IP address the connection is made to
+/// when the address is an IP address already. `server_name` overrides it.
+///
+/// ```rust,no_run
+/// use memento::{MementoBuilder, TlsConfig};
+///
+/// #[tokio::main]
+/// async fn main() -> memento::Result<()> {
+///     let tls = TlsConfig::default()
+///         .ca_pem(&std::fs::read("ca.pem")?)?
+///         .client_auth_pem(&std::fs::read("client.pem")?, &std::fs::read("client.key")?)?
+///         .server_name("cache.internal")?;
+///
+///     let memento = MementoBuilder::default()
+///         .tls(tls)
+///         .connect("10.0.1.5:11211")
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct TlsConfig {
+    roots: RootCertStore,
+    identity: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+    server_name: Option<ServerName<'static>>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            roots: RootCertStore::empty(),
+            identity: None,
+            server_name: None,
+        }
+    }
+}
+
+impl Clone for TlsConfig {
+    fn clone(&self) -> Self {
+        Self {
+            roots: self.roots.clone(),
+            identity: self
+                .identity
+                .as_ref()
+                .map(|(chain, key)| (chain.clone(), key.clone_key())),
+            server_name: self.server_name.clone(),
+        }
+    }
+}
+
+impl Debug for TlsConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("roots", &self.roots.len())
+            .field("client_auth", &self.identity.is_some())
+            .field("server_name", &self.server_name)
+            .finish()
+    }
+}
+
+impl TlsConfig {
+    /// Trusts every certificate of the PEM bundle.
+    ///
+    /// ```rust
+    /// use memento::{CommandResp, MementoBuilder, MementoError, TlsConfig};
+    /// use std::sync::Arc;
+    /// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    /// use tokio::net::TcpListener;
+    /// use tokio_rustls::rustls::crypto::ring;
+    /// use tokio_rustls::rustls::pki_types::PrivateKeyDer;
+    /// use tokio_rustls::rustls::ServerConfig;
+    /// use tokio_rustls::TlsAcceptor;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let server = rcgen::generate_simple_self_signed(["localhost".to_string()])?;
+    ///     let other = rcgen::generate_simple_self_signed(["localhost".to_string()])?;
+    ///
+    ///     let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+    ///         .with_safe_default_protocol_versions()?
+    ///         .with_no_client_auth()
+    ///         .with_single_cert(
+    ///             vec![server.cert.der().clone()],
+    ///             PrivateKeyDer::Pkcs8(server.key_pair.serialize_der().into()),
+    ///         )?;
+    ///
+    ///     let acceptor = TlsAcceptor::from(Arc::new(config));
+    ///     let listener = TcpListener::bind("127.0.0.1:0").await?;
+    ///     let addr = listener.local_addr()?;
+    ///
+    ///     tokio::spawn(async move {
+    ///         while let Ok((stream, _)) = listener.accept().await {
+    ///             let acceptor = acceptor.clone();
+    ///
+    ///             tokio::spawn(async move {
+    ///                 let mut stream = acceptor.accept(stream).await?;
+    ///                 let mut request = [0; 9];
+    ///
+    ///                 stream.read_exact(&mut request).await?;
+    ///                 stream.write_all(b"VERSION 1.6.21\r\n").await?;
+    ///                 stream.flush().await
+    ///             });
+    ///         }
+    ///     });
+    ///
+    ///     let trusted = TlsConfig::default().ca_pem(server.cert.pem().as_bytes())?;
+    ///
+    ///     // the certificate is verified against the host of the address.
+    ///     let mut memento = MementoBuilder::default()
+    ///         .tls(trusted.clone())
+    ///         .connect(("localhost", addr.port()))
+    ///         .await?;
+    ///
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(version) if version == "1.6.21"));
+    ///
+    ///     // the certificate has no IP address, unless the name is set explicitly.
+    ///     let result = MementoBuilder::default().tls(trusted.clone()).connect(addr).await;
+    ///
+    ///     assert!(matches!(result, Err(MementoError::TlsError(..))));
+    ///
+    ///     let mut memento = MementoBuilder::default()
+    ///         .tls(trusted.server_name("localhost")?)
+    ///         .connect(addr)
+    ///         .await?;
+    ///
+    ///     assert!(matches!(memento.version().await?, CommandResp::Version(..)));
+    ///
+    ///     let untrusted = TlsConfig::default().ca_pem(other.cert.pem().as_bytes())?;
+    ///
+    ///     let result = MementoBuilder::default()
+    ///         .tls(untrusted)
+    ///         .connect(("localhost", addr.port()))
+    ///         .await;
+    ///
+    ///     assert!(matches!(result, Err(MementoError::TlsError(..))));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn ca_pem(mut self, pem: &[u8]) -> crate::Result<Self> {
+        for cert in CertificateDer::pem_slice_iter(pem) {
+            let cert = cert.map_err(|err| MementoError::TlsError(err.to_string()))?;
+
+            self.roots
+                .add(cert)
+                .map_err(|err| MementoError::TlsError(err.to_string()))?;
+        }
+
+        Ok(self)
+    }
+
+    /// Certificate chain and private key presented to servers that require client
+    /// certificates, the chain starts with the client certificate.
+    pub fn client_auth_pem(mut self, chain: &[u8], key: &[u8]) -> crate::Result<Self> {
+        let chain = CertificateDer::pem_slice_iter(chain)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| MementoError::TlsError(err.to_string()))?;
+
+        let key = PrivateKeyDer::from_pem_slice(key)
+            .map_err(|err| MementoError::TlsError(err.to_string()))?;
+
+        self.identity = Some((chain, key));
+
+        Ok(self)
+    }
+
+    /// Name sent as SNI and verified against the server certificate.
+    pub fn server_name(mut self, name: &str) -> crate::Result<Self> {
+        let name = ServerName::try_from(name)
+            .map_err(|err| MementoError::TlsError(format!("{name}: {err}")))?;
+
+        self.server_name = Some(name.to_owned());
+
+        Ok(self)
+    }
+
+    /// Builds the client config once, so reconnects may resume the session.
+    pub(crate) fn connector(&self, host: Option<&str>) -> crate::Result<Connector> {
+        if self.roots.is_empty() {
+            return Err(MementoError::TlsError(
+                "no trusted CA certificates".to_string(),
+            ));
+        }
+
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|err| MementoError::TlsError(err.to_string()))?
+            .with_root_certificates(self.roots.clone());
+
+        let config = match &self.identity {
+            Some((chain, key)) => builder
+                .with_client_auth_cert(chain.clone(), key.clone_key())
+                .map_err(|err| MementoError::TlsError(err.to_string()))?,
+            None => builder.with_no_client_auth(),
+        };
+
+        let server_name = match (&self.server_name, host) {
+            (Some(name), _) => Some(name.clone()),
+            (None, Some(host)) => Some(
+                ServerName::try_from(host.to_string())
+                    .map_err(|err| MementoError::TlsError(format!("{host}: {err}")))?,
+            ),
+            (None, None) => None,
+        };
+
+        Ok(Connector {
+            connector: TlsConnector::from(Arc::new(config)),
+            server_name,
+        })
+    }
+}
+
+/// Wraps TCP streams of a connection in TLS.
+#[derive(Clone)]
+pub(crate) struct Connector {
+    connector: TlsConnector,
+    server_name: Option<ServerName<'static>>,
+}
+
+impl Debug for Connector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Connector")
+            .field("server_name", &self.server_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Connector {
+    pub(crate) async fn connect(&self, stream: TcpStream) -> crate::Result<TlsStream<TcpStream>> {
+        let server_name = match &self.server_name {
+            Some(name) => name.clone(),
+            None => ServerName::IpAddress(stream.peer_addr()?.ip().into()),
+        };
+
+        self.connector
+            .connect(server_name, stream)
+            .await
+            .map_err(handshake_error)
+    }
+}
+
+/// Rejected certificates and other protocol failures come wrapped in `io::Error`.
+fn handshake_error(err: Error) -> MementoError {
+    match err
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<tokio_rustls::rustls::Error>())
+    {
+        Some(err) => MementoError::TlsError(err.to_string()),
+        None => MementoError::from(err),
+    }
+}